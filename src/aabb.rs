@@ -1,6 +1,6 @@
-use std::{mem, ops::Add};
+use std::ops::Add;
 
-use crate::{interval::Interval, ray::Ray, vec3::Vec3, Point3};
+use crate::{interval::Interval, ray::Ray, stats, vec3::Vec3, Point3};
 
 #[derive(Debug, Clone)]
 pub struct AABB {
@@ -42,22 +42,24 @@ impl AABB {
     }
 
     pub fn hit(&self, r: &Ray, ray_t: &Interval) -> bool {
-        for i in 0..3 {
-            let inv_d = 1. / r.direction[i];
-            let mut t0 = (self.axis(i).min - r.origin[i]) * inv_d;
-            let mut t1 = (self.axis(i).max - r.origin[i]) * inv_d;
-            if inv_d < 0. {
-                mem::swap(&mut t0, &mut t1);
-            }
-
-            let ray_tmin = t0.max(ray_t.min);
-            let ray_tmax = t1.min(ray_t.max);
+        self.clip(r, ray_t).is_some_and(|(start, end)| start < end)
+    }
 
-            if ray_tmax <= ray_tmin {
-                return false;
-            }
+    /// The part of the ray's interval that lies inside the box, as the
+    /// distances where it enters and leaves.
+    pub fn clip(&self, r: &Ray, ray_t: &Interval) -> Option<(f64, f64)> {
+        stats::record_aabb_test();
+
+        let (mut start, mut end) = (ray_t.min, ray_t.max);
+        for axis in 0..3 {
+            let inv_d = 1. / r.direction[axis];
+            let bounds = self.axis(axis);
+            let t0 = (bounds.min - r.origin[axis]) * inv_d;
+            let t1 = (bounds.max - r.origin[axis]) * inv_d;
+            start = start.max(t0.min(t1));
+            end = end.min(t0.max(t1));
         }
-        true
+        (start <= end).then_some((start, end))
     }
 
     pub fn pad(&self) -> Self {
@@ -156,4 +158,21 @@ mod tests {
 
         assert!(!bb.hit(&r, &interval::UNIVERSE));
     }
+
+    #[test]
+    fn clip_to_box() {
+        let bb = AABB::from_points(Point3::new(1., 1., 1.), Point3::new(2., 3., 2.));
+
+        let r = Ray::new(Point3::new(0., 1.5, 1.5), Vec3::new(1., 0., 0.), 0.);
+        assert_eq!(bb.clip(&r, &interval::UNIVERSE), Some((1., 2.)));
+        assert_eq!(
+            bb.clip(&r, &interval::Interval::new(1.5, 10.)),
+            Some((1.5, 2.))
+        );
+
+        // Overlaps the box along each axis separately, but never all at once
+        let r = Ray::new(Point3::new(0., 6., 1.5), Vec3::new(1., -1., 0.), 0.);
+        assert_eq!(bb.clip(&r, &interval::UNIVERSE), None);
+        assert!(!bb.hit(&r, &interval::UNIVERSE));
+    }
 }
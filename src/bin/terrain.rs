@@ -0,0 +1,62 @@
+use std::{error::Error, path::Path, sync::Arc};
+
+use lumiere::{camera, image, material, object, scene::Scene, texture, vec3::Vec3, Colour, Point3};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Image parameters
+    const ASPECT_RATIO: f64 = 16. / 9.;
+    const IMAGE_WIDTH: usize = 1024;
+    const IMAGE_HEIGHT: usize = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as usize;
+    let samples_per_pixel: usize = 500;
+    let max_depth = 50;
+
+    // Pixel array as height * rows * channels 8 bit values
+    const BUFFER_LENGTH: usize = 3 * IMAGE_WIDTH * IMAGE_HEIGHT;
+    let mut pixels = vec![0_u8; BUFFER_LENGTH];
+
+    // Camera
+    let camera = camera::CameraBuilder::new()
+        .origin(Point3::new(0., 400., -900.))
+        .look_at(Point3::new(0., 0., 0.))
+        .fov(40.)
+        .aspect_ratio(ASPECT_RATIO)
+        .aperture(0.)
+        .focus_dist(10.)
+        .build();
+
+    // World
+    let mut world = object::HittableList::new();
+
+    // Ground terrain
+    let ground = Arc::new(material::Lambertian::from_colour(Colour::new(
+        0.48, 0.83, 0.53,
+    )));
+    world.add(Arc::new(object::Heightfield::from_texture(
+        &texture::NoiseTexture::with_scale(0.005),
+        201,
+        201,
+        Point3::new(-1000., 0., -1000.),
+        Vec3::new(2000., 200., 2000.),
+        ground,
+    )));
+
+    // Create scene
+    let scene = Scene::new(
+        world,
+        camera,
+        max_depth,
+        samples_per_pixel,
+        IMAGE_WIDTH,
+        IMAGE_HEIGHT,
+        Colour::new(0.7, 0.8, 1.),
+    );
+
+    // Render the scene to a frame buffer
+    scene.render(&mut pixels)?;
+
+    // Write the frame buffer to a file
+    image::png::write_image::<&Path, IMAGE_WIDTH, IMAGE_HEIGHT>(&pixels, Path::new("image.png"))?;
+    eprintln!("Saved image");
+
+    Ok(())
+}
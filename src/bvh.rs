@@ -6,6 +6,7 @@ use crate::{
     aabb::AABB,
     interval::{self, Interval},
     object::{Hittable, HittableList},
+    stats,
 };
 
 #[derive(Debug)]
@@ -90,6 +91,8 @@ impl Hittable for BVHNode {
         ray_t: &crate::interval::Interval,
         rng: &mut rngs::SmallRng,
     ) -> Option<crate::object::HitRecord> {
+        stats::record_bvh_node();
+
         if !self.bbox.hit(r, ray_t) {
             return None;
         }
@@ -115,4 +118,8 @@ impl Hittable for BVHNode {
     fn bounding_box(&self) -> &AABB {
         &self.bbox
     }
+
+    fn children(&self) -> Vec<&Arc<dyn Hittable>> {
+        self.left.iter().chain(&self.right).collect()
+    }
 }
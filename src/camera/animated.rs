@@ -0,0 +1,398 @@
+use crate::{ray::Ray, vec3::Vec3, Point3};
+
+use super::{Aperture, Camera, FilmPoint};
+
+/// How an animated camera moves between its keyframes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Straight lines between keyframes, with sudden changes of direction.
+    Linear,
+    /// A smooth curve passing through every keyframe.
+    CatmullRom,
+    /// Cubic Bezier curves passing through every third keyframe, using the
+    /// two keyframes in between as control points. There should be `3n + 1`
+    /// keyframes with strictly increasing times. Each curve is timed by the
+    /// keyframes at its ends, so the times of the control points only need to
+    /// fall between them.
+    Bezier,
+}
+
+/// The state of a camera at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    pub time: f64,
+    pub origin: Point3,
+    pub look_at: Point3,
+    pub v_up: Vec3,
+    /// Vertical field of view in degrees.
+    pub fov: f64,
+    pub focus_dist: f64,
+}
+
+impl Keyframe {
+    pub fn new(time: f64, origin: Point3, look_at: Point3) -> Self {
+        Self {
+            time,
+            origin,
+            look_at,
+            v_up: Vec3::new(0., 1., 0.),
+            fov: 40.,
+            focus_dist: 10.,
+        }
+    }
+
+    pub fn with_v_up(mut self, v_up: Vec3) -> Self {
+        self.v_up = v_up;
+        self
+    }
+
+    pub fn with_fov(mut self, fov: f64) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    pub fn with_focus_dist(mut self, focus_dist: f64) -> Self {
+        self.focus_dist = focus_dist;
+        self
+    }
+
+    /// Blends keyframes together using the given weights, which should sum
+    /// to one.
+    fn blend(weighted: &[(f64, &Keyframe)], time: f64) -> Self {
+        let mut blended = Keyframe {
+            time,
+            origin: Point3::zeros(),
+            look_at: Point3::zeros(),
+            v_up: Vec3::zeros(),
+            fov: 0.,
+            focus_dist: 0.,
+        };
+        for &(weight, keyframe) in weighted {
+            blended.origin += keyframe.origin * weight;
+            blended.look_at += keyframe.look_at * weight;
+            blended.v_up += keyframe.v_up * weight;
+            blended.fov += keyframe.fov * weight;
+            blended.focus_dist += keyframe.focus_dist * weight;
+        }
+        blended
+    }
+}
+
+/// A thin lens camera that follows a path through a series of keyframes.
+///
+/// Scene time is mapped onto the path through the shutter interval, so each
+/// ray sees the camera where it was at that instant, blurring the image as
+/// the camera moves. Rendering a sequence of frames is done by moving the
+/// shutter interval along for each frame.
+#[derive(Debug, Clone)]
+pub struct AnimatedCamera {
+    keyframes: Vec<Keyframe>,
+    interpolation: Interpolation,
+    aspect_ratio: f64,
+    aperture: f64,
+    aperture_shape: Aperture,
+    shutter: (f64, f64),
+}
+
+impl AnimatedCamera {
+    /// Creates a camera following a path through the given keyframes, of
+    /// which there must be at least one.
+    pub fn new(interpolation: Interpolation, keyframes: Vec<Keyframe>) -> Self {
+        assert!(!keyframes.is_empty(), "animated camera has no keyframes");
+        let mut camera = Self {
+            keyframes: Vec::with_capacity(keyframes.len()),
+            interpolation,
+            aspect_ratio: 16. / 9.,
+            aperture: 0.,
+            aperture_shape: Aperture::Circular,
+            shutter: (0., 1.),
+        };
+        for keyframe in keyframes {
+            camera.keyframe(keyframe);
+        }
+        camera
+    }
+
+    /// Adds a keyframe to the path, keeping the keyframes in time order.
+    /// Bezier control points can't be reordered by time, so for Bezier paths
+    /// each keyframe must come after the last.
+    pub fn keyframe(&mut self, keyframe: Keyframe) -> &mut Self {
+        if self.interpolation == Interpolation::Bezier {
+            assert!(
+                self.keyframes
+                    .last()
+                    .is_none_or(|last| keyframe.time > last.time),
+                "bezier keyframes must have strictly increasing times"
+            );
+        }
+        let index = self.keyframes.partition_point(|k| k.time <= keyframe.time);
+        self.keyframes.insert(index, keyframe);
+        self
+    }
+
+    pub fn aspect_ratio(&mut self, aspect_ratio: f64) -> &mut Self {
+        self.aspect_ratio = aspect_ratio;
+        self
+    }
+
+    pub fn aperture(&mut self, aperture: f64) -> &mut Self {
+        self.aperture = aperture;
+        self
+    }
+
+    pub fn aperture_shape(&mut self, aperture_shape: Aperture) -> &mut Self {
+        self.aperture_shape = aperture_shape;
+        self
+    }
+
+    /// Sets the interval of path time that the shutter is open for. Defaults
+    /// to [0, 1].
+    pub fn shutter(&mut self, open: f64, close: f64) -> &mut Self {
+        self.shutter = (open, close);
+        self
+    }
+
+    /// Evaluates the camera path at the given time, holding the first and
+    /// last keyframes before and after the path.
+    pub fn evaluate(&self, time: f64) -> Keyframe {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len() - 1;
+
+        if self.interpolation == Interpolation::Bezier {
+            return self.evaluate_bezier(time);
+        }
+
+        // Index of the keyframe starting the segment containing the time
+        let i = keyframes.partition_point(|k| k.time <= time);
+        if i == 0 || i > last {
+            let keyframe = keyframes[i.min(last)];
+            return Keyframe { time, ..keyframe };
+        }
+        let i = i - 1;
+        let u = segment_fraction(keyframes[i].time, keyframes[i + 1].time, time);
+
+        match self.interpolation {
+            Interpolation::Linear => {
+                Keyframe::blend(&[(1. - u, &keyframes[i]), (u, &keyframes[i + 1])], time)
+            }
+            _ => {
+                let (u2, u3) = (u * u, u * u * u);
+                Keyframe::blend(
+                    &[
+                        ((-u3 + 2. * u2 - u) / 2., &keyframes[i.saturating_sub(1)]),
+                        ((3. * u3 - 5. * u2 + 2.) / 2., &keyframes[i]),
+                        ((-3. * u3 + 4. * u2 + u) / 2., &keyframes[i + 1]),
+                        ((u3 - u2) / 2., &keyframes[(i + 2).min(last)]),
+                    ],
+                    time,
+                )
+            }
+        }
+    }
+
+    fn evaluate_bezier(&self, time: f64) -> Keyframe {
+        let keyframes = &self.keyframes;
+        let segments = (keyframes.len() - 1) / 3;
+        if segments == 0 {
+            return Keyframe {
+                time,
+                ..keyframes[0]
+            };
+        }
+
+        let segment = (0..segments)
+            .find(|&s| time < keyframes[3 * s + 3].time)
+            .unwrap_or(segments - 1);
+        let p = &keyframes[3 * segment..3 * segment + 4];
+        let u = segment_fraction(p[0].time, p[3].time, time).clamp(0., 1.);
+        let v = 1. - u;
+        Keyframe::blend(
+            &[
+                (v * v * v, &p[0]),
+                (3. * v * v * u, &p[1]),
+                (3. * v * u * u, &p[2]),
+                (u * u * u, &p[3]),
+            ],
+            time,
+        )
+    }
+}
+
+fn segment_fraction(start: f64, end: f64, time: f64) -> f64 {
+    if end > start {
+        (time - start) / (end - start)
+    } else {
+        0.
+    }
+}
+
+/// The viewport of a camera pose on its plane of focus.
+struct Viewport {
+    w: Vec3,
+    u: Vec3,
+    v: Vec3,
+    horizontal: Vec3,
+    vertical: Vec3,
+    upper_left_corner: Point3,
+}
+
+impl AnimatedCamera {
+    fn viewport(&self, pose: &Keyframe) -> Viewport {
+        let w = (pose.origin - pose.look_at).unit();
+        let u = pose.v_up.cross(w).unit();
+        let v = w.cross(u);
+
+        let viewport_height = 2. * (pose.fov.to_radians() / 2.).tan() * pose.focus_dist;
+        let horizontal = u * self.aspect_ratio * viewport_height;
+        let vertical = v * viewport_height;
+        let upper_left_corner = pose.origin - horizontal / 2. + vertical / 2. - w * pose.focus_dist;
+        Viewport {
+            w,
+            u,
+            v,
+            horizontal,
+            vertical,
+            upper_left_corner,
+        }
+    }
+}
+
+impl Camera for AnimatedCamera {
+    fn get_ray(&self, s: f64, t: f64, lens: (f64, f64), time: f64) -> Ray {
+        let time = self.shutter.0 + time * (self.shutter.1 - self.shutter.0);
+        let pose = self.evaluate(time);
+        let view = self.viewport(&pose);
+
+        let (x, y) = self.aperture_shape.sample(lens);
+        let offset = (view.u * x + view.v * y) * (self.aperture / 2.);
+        let target = view.upper_left_corner + view.horizontal * s - view.vertical * t;
+        Ray::new(
+            pose.origin + offset,
+            (target - pose.origin - offset).unit(),
+            time,
+        )
+    }
+
+    /// Projects the point using the camera's pose when the shutter opens.
+    fn project(&self, point: &Point3) -> Option<FilmPoint> {
+        let pose = self.evaluate(self.shutter.0);
+        let view = self.viewport(&pose);
+
+        let d = *point - pose.origin;
+        let depth = -d.dot(view.w);
+        if depth == 0. {
+            return None;
+        }
+        let offset = pose.origin + d * (pose.focus_dist / depth) - view.upper_left_corner;
+        Some(FilmPoint {
+            s: offset.dot(view.horizontal) / view.horizontal.length_squared(),
+            t: -offset.dot(view.vertical) / view.vertical.length_squared(),
+            depth,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{vec3::Vec3, Point3};
+
+    use super::{AnimatedCamera, Camera, Interpolation, Keyframe};
+
+    fn path(interpolation: Interpolation) -> AnimatedCamera {
+        let keyframes = [0., 1., 4., 9.]
+            .into_iter()
+            .enumerate()
+            .map(|(i, x)| {
+                Keyframe::new(i as f64, Point3::new(x, 0., 0.), Point3::new(x, 0., -1.))
+                    .with_fov(30. + 10. * i as f64)
+            })
+            .collect();
+        AnimatedCamera::new(interpolation, keyframes)
+    }
+
+    #[test]
+    #[should_panic(expected = "animated camera has no keyframes")]
+    fn reject_no_keyframes() {
+        AnimatedCamera::new(Interpolation::Linear, Vec::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "bezier keyframes must have strictly increasing times")]
+    fn reject_unordered_bezier_keyframes() {
+        let mut camera = path(Interpolation::Bezier);
+        camera.keyframe(Keyframe::new(
+            0.,
+            Point3::new(10., 0., 0.),
+            Point3::new(10., 0., -1.),
+        ));
+    }
+
+    #[test]
+    fn passes_through_keyframes() {
+        for interpolation in [Interpolation::Linear, Interpolation::CatmullRom] {
+            let camera = path(interpolation);
+            for (i, x) in [0., 1., 4., 9.].into_iter().enumerate() {
+                let pose = camera.evaluate(i as f64);
+                assert!(pose.origin.is_close(&Point3::new(x, 0., 0.)));
+                assert!((pose.fov - (30. + 10. * i as f64)).abs() < 1e-9);
+            }
+        }
+
+        // Bezier curves only pass through the ends of each segment
+        let camera = path(Interpolation::Bezier);
+        assert!(camera
+            .evaluate(0.)
+            .origin
+            .is_close(&Point3::new(0., 0., 0.)));
+        assert!(camera
+            .evaluate(3.)
+            .origin
+            .is_close(&Point3::new(9., 0., 0.)));
+        assert!(camera
+            .evaluate(1.5)
+            .origin
+            .is_close(&Point3::new(3., 0., 0.)));
+    }
+
+    #[test]
+    fn between_keyframes() {
+        let linear = path(Interpolation::Linear);
+        assert!(linear
+            .evaluate(1.5)
+            .origin
+            .is_close(&Point3::new(2.5, 0., 0.)));
+        assert!(linear
+            .evaluate(-1.)
+            .origin
+            .is_close(&Point3::new(0., 0., 0.)));
+        assert!(linear
+            .evaluate(5.)
+            .origin
+            .is_close(&Point3::new(9., 0., 0.)));
+
+        // The positions follow x = t², which Catmull-Rom reproduces away from
+        // the ends
+        let smooth = path(Interpolation::CatmullRom);
+        assert!(smooth
+            .evaluate(1.5)
+            .origin
+            .is_close(&Point3::new(2.25, 0., 0.)));
+    }
+
+    #[test]
+    fn rays_follow_shutter() {
+        let mut camera = path(Interpolation::Linear);
+        camera.shutter(1., 2.);
+
+        let r = camera.get_ray(0.5, 0.5, (0.5, 0.5), 0.5);
+        assert_eq!(r.time, 1.5);
+        assert!(r.origin.is_close(&Point3::new(2.5, 0., 0.)));
+        assert!(r.direction.is_close(&Vec3::new(0., 0., -1.)));
+
+        // Points are projected from where the camera is when the shutter opens
+        let p = camera.project(&Point3::new(1., 0., -5.)).unwrap();
+        assert!((p.s - 0.5).abs() < 1e-9);
+        assert!((p.t - 0.5).abs() < 1e-9);
+        assert!((p.depth - 5.).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,220 @@
+use std::{f64::consts, fs::File, io, path::Path, sync::Arc};
+
+use super::camera;
+
+/// The shape of the opening in a thin lens, which gives out of focus
+/// highlights (bokeh) their shape.
+#[derive(Debug, Clone)]
+pub enum Aperture {
+    /// A perfectly round opening.
+    Circular,
+    /// A regular polygon formed by the given number of straight blades,
+    /// rotated anticlockwise by `rotation` degrees.
+    Polygon { blades: usize, rotation: f64 },
+    /// An arbitrary opening described by a grayscale image.
+    Mask(Arc<ApertureMask>),
+}
+
+impl Aperture {
+    /// Maps a point uniformly distributed in the unit square to a point on
+    /// the aperture, within the unit disk for circular and polygonal
+    /// apertures and within [-1, 1] for masks.
+    pub fn sample(&self, sample: (f64, f64)) -> (f64, f64) {
+        match self {
+            Aperture::Circular => camera::square_to_disk(sample),
+            Aperture::Polygon { blades, rotation } => {
+                sample_polygon(*blades, rotation.to_radians(), sample)
+            }
+            Aperture::Mask(mask) => mask.sample(sample),
+        }
+    }
+}
+
+/// Samples a regular polygon inscribed in the unit circle by choosing one of
+/// the equal triangles fanning out from the centre, then a point within it.
+fn sample_polygon(blades: usize, rotation: f64, sample: (f64, f64)) -> (f64, f64) {
+    let blades = blades.max(3);
+    let scaled = sample.0 * blades as f64;
+    let k = (scaled as usize).min(blades - 1);
+    let a = scaled - k as f64;
+
+    let angle = |i: usize| rotation + consts::TAU * i as f64 / blades as f64;
+    let (a0, a1) = (angle(k), angle(k + 1));
+
+    let su = a.sqrt();
+    let b1 = su * (1. - sample.1);
+    let b2 = su * sample.1;
+    (b1 * a0.cos() + b2 * a1.cos(), b1 * a0.sin() + b2 * a1.sin())
+}
+
+/// A grayscale aperture image, where brighter pixels let through more light.
+/// The image spans the full diameter of the lens.
+#[derive(Debug)]
+pub struct ApertureMask {
+    width: usize,
+    height: usize,
+    /// Cumulative distribution over the rows of the image.
+    row_cdf: Vec<f64>,
+    /// Cumulative distribution over the pixels within each row.
+    pixel_cdf: Vec<f64>,
+}
+
+impl ApertureMask {
+    /// Creates a mask from row-major pixel weights, which must not all be
+    /// zero.
+    pub fn new(width: usize, height: usize, weights: &[f64]) -> Self {
+        assert_eq!(weights.len(), width * height, "wrong number of weights");
+
+        let mut pixel_cdf = Vec::with_capacity(weights.len());
+        let mut row_cdf = Vec::with_capacity(height);
+        let mut total = 0.;
+        for row in weights.chunks_exact(width) {
+            let mut row_total = 0.;
+            for weight in row {
+                row_total += weight.max(0.);
+                pixel_cdf.push(row_total);
+            }
+            // Normalise each row so it can be sampled on its own
+            if row_total > 0. {
+                let start = pixel_cdf.len() - width;
+                pixel_cdf[start..].iter_mut().for_each(|c| *c /= row_total);
+            }
+            total += row_total;
+            row_cdf.push(total);
+        }
+        assert!(total > 0., "aperture mask must let some light through");
+        row_cdf.iter_mut().for_each(|c| *c /= total);
+
+        Self {
+            width,
+            height,
+            row_cdf,
+            pixel_cdf,
+        }
+    }
+
+    /// Loads a mask from a PNG image, using the average of the colour
+    /// channels as the weight of each pixel. Returns an error if the image
+    /// can't be read or is completely black.
+    pub fn open<P>(path: P) -> Result<Self, png::DecodingError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut decoder = png::Decoder::new(File::open(path)?);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        let samples = info.color_type.samples();
+        let channels = if samples >= 3 { 3 } else { 1 };
+        let weights: Vec<f64> = buf[..info.buffer_size()]
+            .chunks_exact(samples)
+            .map(|pixel| pixel[..channels].iter().map(|&c| c as f64).sum::<f64>())
+            .collect();
+        if !weights.iter().any(|&w| w > 0.) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "aperture mask must let some light through",
+            )
+            .into());
+        }
+        Ok(Self::new(
+            info.width as usize,
+            info.height as usize,
+            &weights,
+        ))
+    }
+
+    /// Chooses a point on the mask with probability proportional to the
+    /// brightness of each pixel, with the top of the image towards +y.
+    pub fn sample(&self, sample: (f64, f64)) -> (f64, f64) {
+        let (row, v) = sample_cdf(&self.row_cdf, sample.1);
+        let row_cdf = &self.pixel_cdf[row * self.width..(row + 1) * self.width];
+        let (col, u) = sample_cdf(row_cdf, sample.0);
+
+        let x = (col as f64 + u) / self.width as f64;
+        let y = (row as f64 + v) / self.height as f64;
+        (2. * x - 1., 1. - 2. * y)
+    }
+}
+
+/// Finds the bin of a normalised cumulative distribution containing `xi`,
+/// along with how far through the bin it lies.
+fn sample_cdf(cdf: &[f64], xi: f64) -> (usize, f64) {
+    let i = cdf.partition_point(|&c| c <= xi).min(cdf.len() - 1);
+    let start = if i == 0 { 0. } else { cdf[i - 1] };
+    let width = cdf[i] - start;
+    let offset = if width > 0. {
+        (xi - start) / width
+    } else {
+        0.5
+    };
+    (i, offset.clamp(0., 1.))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        f64::consts,
+        fs::{self, File},
+    };
+
+    use super::{sample_polygon, ApertureMask};
+
+    #[test]
+    fn polygon_samples_stay_inside() {
+        let blades = 6;
+        // Distance from the centre to the middle of each edge
+        let apothem = (consts::PI / blades as f64).cos();
+        for i in 0..50 {
+            for j in 0..50 {
+                let sample = (i as f64 / 50., j as f64 / 50.);
+                let (x, y) = sample_polygon(blades, 0., sample);
+                for k in 0..blades {
+                    let normal = consts::TAU * (k as f64 + 0.5) / blades as f64;
+                    assert!(x * normal.cos() + y * normal.sin() <= apothem + 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mask_only_samples_open_pixels() {
+        // Only the top right quadrant is open
+        let mask = ApertureMask::new(2, 2, &[0., 1., 0., 0.]);
+        for i in 0..20 {
+            for j in 0..20 {
+                let (x, y) = mask.sample((i as f64 / 20., j as f64 / 20.));
+                assert!((0. ..=1.).contains(&x));
+                assert!((0. ..=1.).contains(&y));
+            }
+        }
+    }
+
+    #[test]
+    fn open_indexed_mask() {
+        // Palette entry 0 is white and entry 1 black, so the indices
+        // themselves must not be taken as weights
+        let path = std::env::temp_dir().join(format!("lumiere-mask-{}.png", std::process::id()));
+        let write = |pixels: &[u8]| {
+            let mut encoder = png::Encoder::new(File::create(&path).unwrap(), 2, 2);
+            encoder.set_color(png::ColorType::Indexed);
+            encoder.set_palette(vec![255, 255, 255, 0, 0, 0]);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(pixels).unwrap();
+        };
+
+        write(&[1, 0, 1, 1]);
+        let mask = ApertureMask::open(&path).unwrap();
+        for i in 0..20 {
+            let (x, y) = mask.sample((i as f64 / 20., 0.5));
+            assert!(x >= 0. && y >= 0.);
+        }
+
+        write(&[1, 1, 1, 1]);
+        let black = ApertureMask::open(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(black.is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use std::{f64::consts, fmt};
+
+use rand::{rngs, Rng};
+
+use crate::{object::Hittable, ray::Ray, Point3};
+
+/// Generates the rays leaving a camera for each position on the film.
+pub trait Camera: fmt::Debug + Send + Sync {
+    /// Gets the ray for normalised film coordinates s,t, which are 0,0 at the
+    /// top left corner of the image and 1,1 at the bottom right corner. The
+    /// lens sample is a point uniformly distributed in the unit square, used
+    /// to choose where on the lens the ray passes through, and the time sample
+    /// is uniformly distributed in [0, 1).
+    fn get_ray(&self, s: f64, t: f64, lens: (f64, f64), time: f64) -> Ray;
+
+    /// Finds where a point in the scene appears on the film, ignoring the
+    /// lens and any motion during the exposure. Returns `None` if the camera
+    /// doesn't support projecting points.
+    fn project(&self, _point: &Point3) -> Option<FilmPoint> {
+        None
+    }
+
+    /// Prepares the camera for rendering the given world, such as by focusing
+    /// on it. This is called when the camera is added to a scene.
+    fn focus(&mut self, _world: &dyn Hittable, _rng: &mut rngs::SmallRng) {}
+
+    /// The scale applied to the radiance reaching the film, such as from the
+    /// exposure settings of a physical camera.
+    fn exposure(&self) -> f64 {
+        1.
+    }
+
+    /// Gets the ray for film coordinates s,t along with the weight of the
+    /// light it carries back to the film, or `None` if the ray is blocked
+    /// inside the camera. Cameras that model light passing through a real
+    /// lens override this to account for vignetting.
+    fn get_weighted_ray(&self, s: f64, t: f64, lens: (f64, f64), time: f64) -> Option<(Ray, f64)> {
+        Some((self.get_ray(s, t, lens, time), 1.))
+    }
+
+    /// Gets the weighted ray for normalised film coordinates s,t with a random
+    /// lens and time sample.
+    fn sample_ray(&self, s: f64, t: f64, rng: &mut rngs::SmallRng) -> Option<(Ray, f64)> {
+        let lens = (rng.gen(), rng.gen());
+        self.get_weighted_ray(s, t, lens, rng.gen())
+    }
+}
+
+/// A point in the scene projected onto the film.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilmPoint {
+    /// Normalised film coordinates, as used by `Camera::get_ray`.
+    pub s: f64,
+    pub t: f64,
+    /// Distance of the point in front of the camera, which is negative for
+    /// points behind the camera.
+    pub depth: f64,
+}
+
+impl FilmPoint {
+    pub fn is_behind_camera(&self) -> bool {
+        self.depth <= 0.
+    }
+
+    /// Checks whether the point lies in front of the camera and within the
+    /// image.
+    pub fn is_on_film(&self) -> bool {
+        !self.is_behind_camera() && (0. ..=1.).contains(&self.s) && (0. ..=1.).contains(&self.t)
+    }
+}
+
+/// Maps a point in the unit square to a point in the unit disk, preserving
+/// relative areas so that uniform samples stay uniform.
+pub fn square_to_disk(sample: (f64, f64)) -> (f64, f64) {
+    // Concentric mapping, which keeps strata from the square compact
+    let a = 2. * sample.0 - 1.;
+    let b = 2. * sample.1 - 1.;
+    if a == 0. && b == 0. {
+        return (0., 0.);
+    }
+
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, consts::FRAC_PI_4 * (b / a))
+    } else {
+        (b, consts::FRAC_PI_2 - consts::FRAC_PI_4 * (a / b))
+    };
+    (r * theta.cos(), r * theta.sin())
+}
@@ -0,0 +1,13 @@
+pub mod animated;
+pub mod aperture;
+pub mod camera;
+pub mod pinhole;
+pub mod realistic;
+pub mod standard;
+
+pub use animated::{AnimatedCamera, Interpolation, Keyframe};
+pub use aperture::{Aperture, ApertureMask};
+pub use camera::{Camera, FilmPoint};
+pub use pinhole::{BrownConrady, Intrinsics, PinholeCamera};
+pub use realistic::{LensElement, RealisticCamera};
+pub use standard::{CameraBuilder, FisheyeMapping, Projection, StandardCamera};
@@ -0,0 +1,227 @@
+use crate::{ray::Ray, vec3::Vec3, Point3};
+
+use super::{Camera, FilmPoint};
+
+/// The intrinsic parameters of a calibrated camera, in pixels, following the
+/// usual computer vision convention where pixel centres lie at integer
+/// coordinates, x increases to the right and y increases downwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Intrinsics {
+    /// Focal length in pixels along the x axis.
+    pub fx: f64,
+    /// Focal length in pixels along the y axis.
+    pub fy: f64,
+    /// Principal point x coordinate.
+    pub cx: f64,
+    /// Principal point y coordinate.
+    pub cy: f64,
+    /// Skew between the x and y axes, which is zero for most sensors.
+    pub skew: f64,
+    pub image_width: usize,
+    pub image_height: usize,
+}
+
+impl Intrinsics {
+    pub fn new(
+        fx: f64,
+        fy: f64,
+        cx: f64,
+        cy: f64,
+        image_width: usize,
+        image_height: usize,
+    ) -> Self {
+        Self {
+            fx,
+            fy,
+            cx,
+            cy,
+            skew: 0.,
+            image_width,
+            image_height,
+        }
+    }
+
+    /// Creates intrinsics from a row-major 3x3 camera matrix.
+    pub fn from_matrix(k: [[f64; 3]; 3], image_width: usize, image_height: usize) -> Self {
+        Self {
+            fx: k[0][0],
+            fy: k[1][1],
+            cx: k[0][2],
+            cy: k[1][2],
+            skew: k[0][1],
+            image_width,
+            image_height,
+        }
+    }
+}
+
+/// Brown–Conrady lens distortion, with radial coefficients k1, k2, k3 and
+/// tangential coefficients p1, p2, as used by common calibration tools.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BrownConrady {
+    pub k1: f64,
+    pub k2: f64,
+    pub k3: f64,
+    pub p1: f64,
+    pub p2: f64,
+}
+
+impl BrownConrady {
+    pub fn new(k1: f64, k2: f64, k3: f64, p1: f64, p2: f64) -> Self {
+        Self { k1, k2, k3, p1, p2 }
+    }
+
+    /// Applies the distortion to an undistorted point on the normalised image
+    /// plane.
+    pub fn distort(&self, x: f64, y: f64) -> (f64, f64) {
+        let r2 = x * x + y * y;
+        let radial = 1. + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+        let dx = 2. * self.p1 * x * y + self.p2 * (r2 + 2. * x * x);
+        let dy = self.p1 * (r2 + 2. * y * y) + 2. * self.p2 * x * y;
+        (x * radial + dx, y * radial + dy)
+    }
+
+    /// Removes the distortion from a distorted point on the normalised image
+    /// plane, by fixed point iteration since there is no closed form inverse.
+    pub fn undistort(&self, xd: f64, yd: f64) -> (f64, f64) {
+        let (mut x, mut y) = (xd, yd);
+        for _ in 0..20 {
+            let r2 = x * x + y * y;
+            let radial = 1. + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+            let dx = 2. * self.p1 * x * y + self.p2 * (r2 + 2. * x * x);
+            let dy = self.p1 * (r2 + 2. * y * y) + 2. * self.p2 * x * y;
+            x = (xd - dx) / radial;
+            y = (yd - dy) / radial;
+        }
+        (x, y)
+    }
+}
+
+/// An ideal pinhole camera described by calibrated intrinsics and lens
+/// distortion, so that renders line up with images from a real sensor.
+#[derive(Debug)]
+pub struct PinholeCamera {
+    origin: Point3,
+    intrinsics: Intrinsics,
+    distortion: BrownConrady,
+    /// Camera axes in world space, with z pointing forward and y pointing
+    /// down the image.
+    x_axis: Vec3,
+    y_axis: Vec3,
+    z_axis: Vec3,
+}
+
+impl PinholeCamera {
+    /// Creates a new camera at `origin` looking towards `look_at`, with the
+    /// top of the image towards `v_up`.
+    pub fn new(
+        intrinsics: Intrinsics,
+        distortion: BrownConrady,
+        origin: Point3,
+        look_at: Point3,
+        v_up: Vec3,
+    ) -> Self {
+        let z_axis = (look_at - origin).unit();
+        let x_axis = z_axis.cross(v_up).unit();
+        let y_axis = z_axis.cross(x_axis);
+        Self {
+            origin,
+            intrinsics,
+            distortion,
+            x_axis,
+            y_axis,
+            z_axis,
+        }
+    }
+
+    /// Converts normalised film coordinates into pixel coordinates. Each
+    /// pixel covers a unit square centred on its integer coordinate.
+    fn film_to_pixel(&self, s: f64, t: f64) -> (f64, f64) {
+        let intrinsics = &self.intrinsics;
+        (
+            s * (intrinsics.image_width - 1) as f64 - 0.5,
+            t * (intrinsics.image_height - 1) as f64 - 0.5,
+        )
+    }
+}
+
+impl Camera for PinholeCamera {
+    fn get_ray(&self, s: f64, t: f64, _lens: (f64, f64), time: f64) -> Ray {
+        let (px, py) = self.film_to_pixel(s, t);
+        let k = &self.intrinsics;
+
+        // Distorted coordinates on the normalised image plane
+        let yd = (py - k.cy) / k.fy;
+        let xd = (px - k.cx - k.skew * yd) / k.fx;
+        let (x, y) = self.distortion.undistort(xd, yd);
+
+        let direction = self.x_axis * x + self.y_axis * y + self.z_axis;
+        Ray::new(self.origin, direction, time)
+    }
+
+    fn project(&self, point: &Point3) -> Option<FilmPoint> {
+        let d = *point - self.origin;
+        let depth = d.dot(self.z_axis);
+        if depth == 0. {
+            return None;
+        }
+
+        let (xd, yd) = self
+            .distortion
+            .distort(d.dot(self.x_axis) / depth, d.dot(self.y_axis) / depth);
+        let k = &self.intrinsics;
+        let px = k.fx * xd + k.skew * yd + k.cx;
+        let py = k.fy * yd + k.cy;
+        Some(FilmPoint {
+            s: (px + 0.5) / (k.image_width - 1) as f64,
+            t: (py + 0.5) / (k.image_height - 1) as f64,
+            depth,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{vec3::Vec3, Point3};
+
+    use super::{BrownConrady, Camera, Intrinsics, PinholeCamera};
+
+    #[test]
+    fn undistort_inverts_distort() {
+        let distortion = BrownConrady::new(-0.28, 0.07, 0.001, 0.0002, -0.0004);
+        let (xd, yd) = distortion.distort(0.3, -0.2);
+        let (x, y) = distortion.undistort(xd, yd);
+
+        assert!((x - 0.3).abs() < 1e-9);
+        assert!((y + 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn principal_point_looks_forward() {
+        let intrinsics = Intrinsics::new(500., 500., 49.5, 29.5, 100, 60);
+        let camera = PinholeCamera::new(
+            intrinsics,
+            BrownConrady::new(-0.2, 0.05, 0., 0., 0.),
+            Point3::new(0., 0., 0.),
+            Point3::new(0., 0., 5.),
+            Vec3::new(0., 1., 0.),
+        );
+
+        // Film coordinates for the centre of pixel 49.5, 29.5
+        let s = 50. / 99.;
+        let t = 30. / 59.;
+        let r = camera.get_ray(s, t, (0.5, 0.5), 0.);
+        assert!(r.direction.is_close(&Vec3::new(0., 0., 1.)));
+
+        // Pixel x increases to the right, which is -x when looking along +z
+        // with y up
+        let r = camera.get_ray(1., 0., (0.5, 0.5), 0.);
+        assert!(r.direction.x < 0.);
+        assert!(r.direction.y > 0.);
+
+        let p = camera.project(&r.at(3.)).unwrap();
+        assert!((p.s - 1.).abs() < 1e-6);
+        assert!(p.t.abs() < 1e-6);
+        assert!(p.depth > 0.);
+    }
+}
@@ -0,0 +1,472 @@
+use std::{fs, io, path::Path};
+
+use crate::{ray::Ray, vec3::Vec3, Point3};
+
+use super::{camera, Camera};
+
+/// One surface of a lens prescription, with lengths in millimetres.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LensElement {
+    /// Radius of curvature, positive when the centre of curvature lies
+    /// towards the film. A radius of zero marks the aperture stop.
+    pub radius: f64,
+    /// Distance along the optical axis to the next surface, or to the film
+    /// for the last surface.
+    pub thickness: f64,
+    /// Index of refraction of the material between this surface and the
+    /// next, where 0 means air.
+    pub ior: f64,
+    /// Diameter of the surface.
+    pub aperture: f64,
+}
+
+impl LensElement {
+    pub fn new(radius: f64, thickness: f64, ior: f64, aperture: f64) -> Self {
+        Self {
+            radius,
+            thickness,
+            ior,
+            aperture,
+        }
+    }
+
+    fn is_stop(&self) -> bool {
+        self.radius == 0.
+    }
+
+    /// The index of refraction behind the surface, treating 0 as air.
+    fn film_side_ior(&self) -> f64 {
+        if self.ior == 0. {
+            1.
+        } else {
+            self.ior
+        }
+    }
+}
+
+/// Parses a lens prescription table, listing the surfaces from the front of
+/// the lens to the back with one surface per line as radius, thickness,
+/// index of refraction and aperture diameter. Blank lines and lines starting
+/// with `#` are ignored.
+pub fn parse_prescription(table: &str) -> io::Result<Vec<LensElement>> {
+    let mut elements = Vec::new();
+    for (number, line) in table.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |reason: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", number + 1, reason),
+            )
+        };
+        let values = line
+            .split_whitespace()
+            .map(|v| {
+                v.parse::<f64>()
+                    .map_err(|e| invalid(format!("{}: {}", v, e)))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        match values[..] {
+            [radius, thickness, ior, aperture] => {
+                elements.push(LensElement::new(radius, thickness, ior, aperture))
+            }
+            _ => {
+                return Err(invalid(format!(
+                    "expected 4 values, found {}",
+                    values.len()
+                )))
+            }
+        }
+    }
+
+    if elements.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "lens prescription has no surfaces",
+        ));
+    }
+    Ok(elements)
+}
+
+/// Reads a lens prescription table from a file, as described by
+/// [`parse_prescription`].
+pub fn read_prescription<P: AsRef<Path>>(path: P) -> io::Result<Vec<LensElement>> {
+    parse_prescription(&fs::read_to_string(path)?)
+}
+
+/// A camera that traces rays through each surface of a real lens design.
+///
+/// Rays start on the film and are refracted through the lens elements from
+/// back to front, so vignetting, distortion and focus breathing come from the
+/// lens itself. Rays blocked by the edge of an element or the aperture stop
+/// carry no light. The camera is focused by moving the lens towards or away
+/// from the film.
+///
+/// Internally the camera works in millimetres, with the film at z = 0 and
+/// the lens along +z.
+#[derive(Debug, Clone)]
+pub struct RealisticCamera {
+    elements: Vec<LensElement>,
+    origin: Point3,
+    right: Vec3,
+    up: Vec3,
+    forward: Vec3,
+    sensor_size: (f64, f64),
+    units_per_metre: f64,
+    focus_dist: Option<f64>,
+    shutter: (f64, f64),
+}
+
+impl RealisticCamera {
+    /// Creates a new camera at `origin` looking towards `look_at`, using the
+    /// given lens prescription, which must have at least one surface. The
+    /// thickness of the last element sets the distance to the film until the
+    /// camera is focused.
+    pub fn new(elements: Vec<LensElement>, origin: Point3, look_at: Point3, v_up: Vec3) -> Self {
+        assert!(
+            !elements.is_empty(),
+            "lens prescription must have at least one surface"
+        );
+        let forward = (look_at - origin).unit();
+        let right = forward.cross(v_up).unit();
+        let up = right.cross(forward);
+        Self {
+            elements,
+            origin,
+            right,
+            up,
+            forward,
+            sensor_size: (36., 24.),
+            units_per_metre: 1.,
+            focus_dist: None,
+            shutter: (0., 1.),
+        }
+    }
+
+    /// Sets the width and height of the film in millimetres. Defaults to a
+    /// 36x24mm full frame sensor.
+    pub fn sensor_size(&mut self, width: f64, height: f64) -> &mut Self {
+        self.sensor_size = (width, height);
+        self
+    }
+
+    /// Sets how many scene units make up a metre. Defaults to 1.
+    pub fn units_per_metre(&mut self, units_per_metre: f64) -> &mut Self {
+        self.units_per_metre = units_per_metre;
+        self.refocus();
+        self
+    }
+
+    /// Focuses the lens on objects at the given distance from the film in
+    /// scene units.
+    pub fn focus_dist(&mut self, focus_dist: f64) -> &mut Self {
+        self.focus_dist = Some(focus_dist);
+        self.refocus();
+        self
+    }
+
+    /// Sets the interval of scene time that the shutter is open for.
+    /// Defaults to [0, 1].
+    pub fn shutter(&mut self, open: f64, close: f64) -> &mut Self {
+        self.shutter = (open, close);
+        self
+    }
+
+    /// The distance from the back of the lens to the film in millimetres.
+    pub fn film_distance(&self) -> f64 {
+        self.elements.last().unwrap().thickness
+    }
+
+    fn mm_to_units(&self) -> f64 {
+        self.units_per_metre / 1000.
+    }
+
+    /// Moves the lens so that objects at the focus distance are sharp, by
+    /// solving for the film distance where a paraxial ray from an object on
+    /// the axis crosses back over the axis.
+    fn refocus(&mut self) {
+        let Some(focus_dist) = self.focus_dist else {
+            return;
+        };
+        let object_z = focus_dist / self.mm_to_units();
+        let height = self.elements[0].aperture * 0.005;
+
+        let image_z = |camera: &Self| {
+            let front_z = camera.elements.iter().map(|e| e.thickness).sum::<f64>();
+            let object = Point3::new(0., 0., object_z);
+            let direction = (Point3::new(height, 0., front_z) - object).unit();
+            // Start just in front of the lens to keep the intersections precise
+            let origin = object + direction * ((front_z + 1. - object_z) / direction.z);
+            let (o, d) = camera.trace(origin, direction, false)?;
+            (d.x != 0.).then(|| o.z - o.x / d.x * d.z)
+        };
+
+        // Secant method on the film distance
+        let mut camera = self.clone();
+        let mut b0 = self.film_distance();
+        let Some(mut g0) = image_z(&camera) else {
+            return;
+        };
+        let mut b1 = b0 + 1.;
+        for _ in 0..50 {
+            camera.elements.last_mut().unwrap().thickness = b1;
+            let Some(g1) = image_z(&camera) else {
+                return;
+            };
+            if g1.abs() < 1e-9 || g1 == g0 {
+                break;
+            }
+            let b2 = b1 - g1 * (b1 - b0) / (g1 - g0);
+            (b0, g0, b1) = (b1, g1, b2);
+        }
+        self.elements = camera.elements;
+    }
+
+    /// Traces a ray through the lens surfaces, either from the film towards
+    /// the scene or from the scene towards the film, returning the ray once
+    /// it has left the lens or `None` if it was blocked.
+    fn trace(
+        &self,
+        mut origin: Point3,
+        mut direction: Vec3,
+        from_film: bool,
+    ) -> Option<(Point3, Vec3)> {
+        // Axial position of each surface's vertex, from the front
+        let mut vertices = Vec::with_capacity(self.elements.len());
+        let mut z = self.elements.iter().map(|e| e.thickness).sum::<f64>();
+        for element in &self.elements {
+            vertices.push(z);
+            z -= element.thickness;
+        }
+
+        let mut order: Vec<usize> = (0..self.elements.len()).collect();
+        if from_film {
+            order.reverse();
+        }
+
+        for i in order {
+            let element = &self.elements[i];
+            let vertex = vertices[i];
+
+            let (t, normal) = if element.is_stop() {
+                ((vertex - origin.z) / direction.z, None)
+            } else {
+                let centre = Point3::new(0., 0., vertex - element.radius);
+                let oc = origin - centre;
+                let half_b = oc.dot(direction);
+                let c = oc.length_squared() - element.radius * element.radius;
+                let discriminant = half_b * half_b - c;
+                if discriminant < 0. {
+                    return None;
+                }
+                // Pick the side of the sphere that the surface lies on
+                let closer = (direction.z < 0.) == (element.radius > 0.);
+                let t = if closer {
+                    -half_b - discriminant.sqrt()
+                } else {
+                    -half_b + discriminant.sqrt()
+                };
+                let normal = (origin + direction * t - centre).unit();
+                (t, Some(normal))
+            };
+            if t.is_nan() || t <= 0. {
+                return None;
+            }
+
+            origin += direction * t;
+            let half_aperture = element.aperture / 2.;
+            if origin.x * origin.x + origin.y * origin.y > half_aperture * half_aperture {
+                return None;
+            }
+
+            if let Some(normal) = normal {
+                let normal = if normal.dot(direction) > 0. {
+                    -normal
+                } else {
+                    normal
+                };
+                let film_side = element.film_side_ior();
+                let scene_side = match i {
+                    0 => 1.,
+                    _ => self.elements[i - 1].film_side_ior(),
+                };
+                let eta = if from_film {
+                    film_side / scene_side
+                } else {
+                    scene_side / film_side
+                };
+
+                // Total internal reflection is blocked by the lens barrel
+                let cos_theta = -direction.dot(normal);
+                if eta * eta * (1. - cos_theta * cos_theta) > 1. {
+                    return None;
+                }
+                direction = direction.refract(&normal, eta).unit();
+            }
+        }
+
+        Some((origin, direction))
+    }
+
+    /// Converts a point and direction from the camera's internal space in
+    /// millimetres into a ray in the scene.
+    fn to_scene(&self, origin: Point3, direction: Vec3, time: f64) -> Ray {
+        let to_world = |v: Vec3| self.right * v.x + self.up * v.y + self.forward * v.z;
+        Ray::new(
+            self.origin + to_world(origin) * self.mm_to_units(),
+            to_world(direction).unit(),
+            time,
+        )
+    }
+
+    /// Gets the ray leaving the film at s,t through a point on the rear
+    /// element, along with its cos⁴ falloff.
+    fn trace_from_film(&self, s: f64, t: f64, lens: (f64, f64)) -> Option<(Point3, Vec3, f64)> {
+        let film = Point3::new(
+            (0.5 - s) * self.sensor_size.0,
+            (t - 0.5) * self.sensor_size.1,
+            0.,
+        );
+        let rear = self.elements.last().unwrap();
+        let (x, y) = camera::square_to_disk(lens);
+        let target = Point3::new(
+            x * rear.aperture / 2.,
+            y * rear.aperture / 2.,
+            rear.thickness,
+        );
+
+        let direction = (target - film).unit();
+        let (origin, direction) = self.trace(film, direction, true)?;
+        let cos_theta = (target - film).unit().z;
+        Some((origin, direction, cos_theta.powi(4)))
+    }
+}
+
+impl Camera for RealisticCamera {
+    /// Gets a ray through the lens, ignoring vignetting. Rays that would be
+    /// blocked fall back to passing through the centre of the rear element,
+    /// or along the optical axis if that is also blocked.
+    fn get_ray(&self, s: f64, t: f64, lens: (f64, f64), time: f64) -> Ray {
+        let time = self.shutter.0 + time * (self.shutter.1 - self.shutter.0);
+        let (origin, direction, _) = self
+            .trace_from_film(s, t, lens)
+            .or_else(|| self.trace_from_film(s, t, (0.5, 0.5)))
+            .unwrap_or((Point3::zeros(), Vec3::new(0., 0., 1.), 0.));
+        self.to_scene(origin, direction, time)
+    }
+
+    fn get_weighted_ray(&self, s: f64, t: f64, lens: (f64, f64), time: f64) -> Option<(Ray, f64)> {
+        let time = self.shutter.0 + time * (self.shutter.1 - self.shutter.0);
+        let (origin, direction, weight) = self.trace_from_film(s, t, lens)?;
+        Some((self.to_scene(origin, direction, time), weight))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{vec3::Vec3, Point3};
+
+    use super::{parse_prescription, Camera, LensElement, RealisticCamera};
+
+    fn biconvex() -> RealisticCamera {
+        let elements = parse_prescription(
+            "# radius thickness ior aperture
+            50 2 1.5 20
+            -50 45 0 20",
+        )
+        .unwrap();
+        let mut camera = RealisticCamera::new(
+            elements,
+            Point3::new(0., 0., 0.),
+            Point3::new(0., 0., -1.),
+            Vec3::new(0., 1., 0.),
+        );
+        // Work in millimetres
+        camera.units_per_metre(1000.);
+        camera
+    }
+
+    #[test]
+    fn focus_at_infinity() {
+        let mut camera = biconvex();
+        camera.focus_dist(1e12);
+
+        // Back focal length of a thick biconvex lens
+        let n: f64 = 1.5;
+        let f = 1. / ((n - 1.) * (2. / 50. - (n - 1.) * 2. / (n * 2500.)));
+        let back_focal_length = f * (1. - (n - 1.) * 2. / (n * 50.));
+        assert!((camera.film_distance() - back_focal_length).abs() < 0.05);
+    }
+
+    #[test]
+    fn rays_converge_on_focus_plane() {
+        let mut camera = biconvex();
+        camera.focus_dist(1000.);
+
+        // Rays from one point on the film through different parts of the
+        // lens meet again near the focus distance, up to lens aberrations
+        let rays: Vec<_> = [(0.5, 0.5), (0.4, 0.5), (0.5, 0.6), (0.6, 0.5), (0.5, 0.4)]
+            .into_iter()
+            .map(|lens| {
+                let (r, weight) = camera.get_weighted_ray(0.45, 0.5, lens, 0.).unwrap();
+                assert!(weight > 0. && weight <= 1.);
+                r
+            })
+            .collect();
+        let spread = |z: f64| {
+            let points: Vec<_> = rays
+                .iter()
+                .map(|r| r.at((z - r.origin.z) / r.direction.z))
+                .collect();
+            points
+                .iter()
+                .map(|p| (*p - points[0]).length())
+                .fold(0., f64::max)
+        };
+        assert!(spread(-1000.) < 0.5);
+        assert!(spread(-1000.) < spread(-800.));
+        assert!(spread(-1000.) < spread(-1250.));
+
+        // The image on the film is inverted
+        assert!(rays[0].at(1000.).x < 0.);
+    }
+
+    #[test]
+    fn stop_blocks_rays() {
+        let elements = vec![
+            LensElement::new(0., 5., 0., 2.),
+            LensElement::new(50., 2., 1.5, 20.),
+            LensElement::new(-50., 45., 0., 20.),
+        ];
+        let camera = RealisticCamera::new(
+            elements,
+            Point3::new(0., 0., 0.),
+            Point3::new(0., 0., -1.),
+            Vec3::new(0., 1., 0.),
+        );
+
+        assert!(camera.get_weighted_ray(0.5, 0.5, (0.5, 0.5), 0.).is_some());
+        assert!(camera.get_weighted_ray(0.5, 0.5, (0.99, 0.5), 0.).is_none());
+    }
+
+    #[test]
+    fn reject_bad_prescription() {
+        assert!(parse_prescription("").is_err());
+        assert!(parse_prescription("50 2 1.5").is_err());
+        assert!(parse_prescription("50 2 glass 20").is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one surface")]
+    fn reject_empty_lens() {
+        RealisticCamera::new(
+            Vec::new(),
+            Point3::zeros(),
+            Point3::new(0., 0., -1.),
+            Vec3::new(0., 1., 0.),
+        );
+    }
+}
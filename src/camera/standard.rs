@@ -0,0 +1,734 @@
+use std::f64::consts;
+
+use rand::rngs;
+
+use crate::{interval::Interval, object::Hittable, ray::Ray, vec3::Vec3, Point3};
+
+use super::{Aperture, Camera, FilmPoint};
+
+/// How the camera projects the scene onto the image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// A thin lens camera with a field of view, where distant objects appear
+    /// smaller.
+    Perspective,
+    /// Parallel rays covering a view of a fixed width and height in scene
+    /// units, where objects appear the same size at any distance.
+    Orthographic { view_width: f64, view_height: f64 },
+    /// A full 360 degree panorama, with longitude across the image and
+    /// latitude down it. The image should have an aspect ratio of 2:1.
+    Equirectangular,
+    /// A fisheye lens covering a circle of the given field of view in
+    /// degrees, which touches the top and bottom of the image.
+    Fisheye { mapping: FisheyeMapping, fov: f64 },
+    /// The six faces of a cube around the camera, each with a 90 degree field
+    /// of view, laid out in a 3x2 grid. The top row holds the right, left and
+    /// up faces, and the bottom row holds the down, front and back faces.
+    CubeMap,
+}
+
+/// How the angle from the centre of a fisheye image relates to the distance
+/// from the centre of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FisheyeMapping {
+    /// The distance from the centre is proportional to the angle.
+    Equidistant,
+    /// Each pixel covers an equal solid angle.
+    Equisolid,
+}
+
+pub struct CameraBuilder {
+    origin: Point3,
+    aspect_ratio: f64,
+    aperture: f64,
+    aperture_shape: Aperture,
+    focus_dist: f64,
+    autofocus: Option<(f64, f64)>,
+    fov: f64,
+    look_dir: Option<Vec3>,
+    look_at: Vec3,
+    v_up: Vec3,
+    projection: Projection,
+    shutter: (f64, f64),
+    sensor_size: (f64, f64),
+    units_per_metre: f64,
+    focal_length: Option<f64>,
+    f_number: Option<f64>,
+    shutter_speed: Option<f64>,
+    iso: Option<f64>,
+}
+
+impl CameraBuilder {
+    pub fn new() -> Self {
+        CameraBuilder {
+            origin: Point3::new(0., 0., -1.),
+            aspect_ratio: 16. / 9.,
+            aperture: 0.,
+            aperture_shape: Aperture::Circular,
+            focus_dist: 10.,
+            autofocus: None,
+            fov: 40.,
+            look_dir: None,
+            look_at: Vec3::new(0., 0., 0.).unit(),
+            v_up: Vec3::new(0., 1., 0.),
+            projection: Projection::Perspective,
+            shutter: (0., 1.),
+            sensor_size: (36., 24.),
+            units_per_metre: 1.,
+            focal_length: None,
+            f_number: None,
+            shutter_speed: None,
+            iso: None,
+        }
+    }
+
+    pub fn origin(&mut self, origin: Point3) -> &mut Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn look_dir(&mut self, look_dir: Vec3) -> &mut Self {
+        self.look_dir = Some(look_dir);
+        self
+    }
+
+    pub fn look_at(&mut self, look_at: Vec3) -> &mut Self {
+        self.look_at = look_at;
+        self
+    }
+
+    pub fn v_up(&mut self, v_up: Vec3) -> &mut Self {
+        self.v_up = v_up;
+        self
+    }
+
+    pub fn aspect_ratio(&mut self, aspect_ratio: f64) -> &mut Self {
+        self.aspect_ratio = aspect_ratio;
+        self
+    }
+
+    pub fn aperture(&mut self, aperture: f64) -> &mut Self {
+        self.aperture = aperture;
+        self
+    }
+
+    pub fn focus_dist(&mut self, focus_dist: f64) -> &mut Self {
+        self.focus_dist = focus_dist;
+        self
+    }
+
+    /// Focuses on whatever is at the centre of the image, replacing
+    /// `focus_dist`.
+    pub fn autofocus(&mut self) -> &mut Self {
+        self.autofocus_at(0.5, 0.5)
+    }
+
+    /// Focuses on whatever is at the normalised film coordinates s,t,
+    /// replacing `focus_dist`. The distance is found from the world when the
+    /// camera is added to a scene, and if nothing is there the focus distance
+    /// is left alone.
+    pub fn autofocus_at(&mut self, s: f64, t: f64) -> &mut Self {
+        self.autofocus = Some((s, t));
+        self
+    }
+
+    /// Sets the vertical field of view of the camera in degrees.
+    pub fn fov(&mut self, fov: f64) -> &mut Self {
+        self.fov = fov;
+        self
+    }
+
+    /// Sets the shape of the lens opening, which gives out of focus
+    /// highlights their shape. Defaults to a circle.
+    pub fn aperture_shape(&mut self, aperture_shape: Aperture) -> &mut Self {
+        self.aperture_shape = aperture_shape;
+        self
+    }
+
+    /// Sets the interval of scene time that the shutter is open for, which
+    /// controls the amount of motion blur. Defaults to [0, 1].
+    pub fn shutter(&mut self, open: f64, close: f64) -> &mut Self {
+        self.shutter = (open, close);
+        self
+    }
+
+    /// Sets the width and height of the sensor in millimetres, used with the
+    /// focal length to find the field of view. Defaults to a 36x24mm full
+    /// frame sensor.
+    pub fn sensor_size(&mut self, width: f64, height: f64) -> &mut Self {
+        self.sensor_size = (width, height);
+        self
+    }
+
+    /// Sets how many scene units make up a metre, which relates the physical
+    /// size of the lens aperture to the scene. Defaults to 1.
+    pub fn units_per_metre(&mut self, units_per_metre: f64) -> &mut Self {
+        self.units_per_metre = units_per_metre;
+        self
+    }
+
+    /// Sets the focal length of the lens in millimetres. The vertical field of
+    /// view is then derived from the sensor height, replacing `fov`.
+    pub fn focal_length(&mut self, focal_length: f64) -> &mut Self {
+        self.focal_length = Some(focal_length);
+        self
+    }
+
+    /// Sets the f-number of the lens, which derives the aperture from the
+    /// focal length, replacing `aperture`.
+    pub fn f_number(&mut self, f_number: f64) -> &mut Self {
+        self.f_number = Some(f_number);
+        self
+    }
+
+    /// Sets the shutter speed in seconds, so the shutter is open from time 0
+    /// until the shutter speed, with scene time measured in seconds.
+    pub fn shutter_speed(&mut self, shutter_speed: f64) -> &mut Self {
+        self.shutter_speed = Some(shutter_speed);
+        self
+    }
+
+    /// Sets the sensitivity of the sensor. Setting the ISO enables a
+    /// photographic exposure derived from the ISO, shutter speed and
+    /// f-number, in which case emitters should be in units of cd/m².
+    pub fn iso(&mut self, iso: f64) -> &mut Self {
+        self.iso = Some(iso);
+        self
+    }
+
+    /// The focal length in millimetres, either as given or derived from the
+    /// field of view.
+    fn effective_focal_length(&self) -> f64 {
+        self.focal_length
+            .unwrap_or_else(|| self.sensor_size.1 / (2. * (self.fov.to_radians() / 2.).tan()))
+    }
+
+    fn effective_fov(&self) -> f64 {
+        match self.focal_length {
+            Some(focal_length) => {
+                (2. * (self.sensor_size.1 / (2. * focal_length)).atan()).to_degrees()
+            }
+            None => self.fov,
+        }
+    }
+
+    fn effective_aperture(&self) -> f64 {
+        match self.f_number {
+            // Aperture diameter in millimetres, converted to scene units
+            Some(f_number) => {
+                self.effective_focal_length() / f_number / 1000. * self.units_per_metre
+            }
+            None => self.aperture,
+        }
+    }
+
+    fn effective_shutter(&self) -> (f64, f64) {
+        match self.shutter_speed {
+            Some(shutter_speed) => (0., shutter_speed),
+            None => self.shutter,
+        }
+    }
+
+    /// The scale from scene radiance to film values, which is 1 unless a
+    /// photographic exposure is used.
+    fn exposure(&self) -> f64 {
+        match self.iso {
+            Some(iso) => {
+                let f_number = self.f_number.unwrap_or(1.);
+                let shutter_speed = self.shutter_speed.unwrap_or(1.);
+                // Saturation based sensitivity, with the brightest value that
+                // doesn't clip at 1
+                let ev100 = (f_number.powi(2) / shutter_speed * 100. / iso).log2();
+                1. / (1.2 * 2_f64.powf(ev100))
+            }
+            None => 1.,
+        }
+    }
+
+    /// Uses an orthographic projection, where the image covers a view of the
+    /// given width and height in scene units. The field of view, aperture and
+    /// focus distance are ignored.
+    pub fn orthographic(&mut self, view_width: f64, view_height: f64) -> &mut Self {
+        self.projection = Projection::Orthographic {
+            view_width,
+            view_height,
+        };
+        self
+    }
+
+    /// Uses an equirectangular projection covering every direction around
+    /// the camera.
+    pub fn equirectangular(&mut self) -> &mut Self {
+        self.projection = Projection::Equirectangular;
+        self
+    }
+
+    /// Uses a fisheye projection with a field of view in degrees, which may
+    /// be larger than 180.
+    pub fn fisheye(&mut self, mapping: FisheyeMapping, fov: f64) -> &mut Self {
+        self.projection = Projection::Fisheye { mapping, fov };
+        self
+    }
+
+    /// Renders the six faces of a cube map around the camera into a single
+    /// image, which should have an aspect ratio of 3:2.
+    pub fn cube_map(&mut self) -> &mut Self {
+        self.projection = Projection::CubeMap;
+        self
+    }
+
+    pub fn build(&mut self) -> StandardCamera {
+        let look_dir = match self.look_dir {
+            Some(look_dir) => look_dir,
+            None => self.look_at - self.origin,
+        };
+
+        let w = -look_dir.unit();
+        let u = self.v_up.cross(w).unit();
+        let v = w.cross(u);
+
+        let (horizontal, vertical, lens_radius, focus_dist) = match self.projection {
+            Projection::Perspective => {
+                let theta = self.effective_fov().to_radians();
+                let h = (theta / 2.).tan();
+                let viewport_height = 2.0 * h;
+                let viewport_width = self.aspect_ratio * viewport_height;
+                (
+                    u * viewport_width * self.focus_dist,
+                    v * viewport_height * self.focus_dist,
+                    self.effective_aperture() / 2.,
+                    self.focus_dist,
+                )
+            }
+            Projection::Orthographic {
+                view_width,
+                view_height,
+            } => (u * view_width, v * view_height, 0., 0.),
+            // Panoramic projections generate rays directly from the camera
+            // basis vectors
+            Projection::Equirectangular | Projection::Fisheye { .. } | Projection::CubeMap => {
+                (Vec3::zeros(), Vec3::zeros(), 0., 0.)
+            }
+        };
+        let upper_left_corner = self.origin - horizontal / 2. + vertical / 2. - w * focus_dist;
+
+        StandardCamera {
+            origin: self.origin,
+            upper_left_corner,
+            horizontal,
+            vertical,
+            lens_radius,
+            focus_dist,
+            autofocus: self.autofocus,
+            aperture_shape: self.aperture_shape.clone(),
+            u,
+            v,
+            w,
+            projection: self.projection,
+            aspect_ratio: self.aspect_ratio,
+            shutter: self.effective_shutter(),
+            exposure: self.exposure(),
+        }
+    }
+}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A camera built by `CameraBuilder`, supporting thin lens, orthographic and
+/// panoramic projections.
+#[derive(Debug)]
+pub struct StandardCamera {
+    origin: Point3,
+    upper_left_corner: Point3,
+    horizontal: Vec3,
+    vertical: Vec3,
+    lens_radius: f64,
+    focus_dist: f64,
+    autofocus: Option<(f64, f64)>,
+    aperture_shape: Aperture,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    projection: Projection,
+    aspect_ratio: f64,
+    shutter: (f64, f64),
+    exposure: f64,
+}
+
+impl StandardCamera {
+    pub fn builder() -> CameraBuilder {
+        CameraBuilder::new()
+    }
+
+    /// The forward, right and up directions of each face of a cube map, in
+    /// row-major order.
+    fn cube_faces(&self) -> [(Vec3, Vec3, Vec3); 6] {
+        let forward = -self.w;
+        [
+            (self.u, -forward, self.v),
+            (-self.u, forward, self.v),
+            (self.v, self.u, -forward),
+            (-self.v, self.u, forward),
+            (forward, self.u, self.v),
+            (-forward, -self.u, self.v),
+        ]
+    }
+
+    /// The position of film coordinates s,t relative to the centre of a
+    /// fisheye image, where the image circle has a radius of one.
+    fn fisheye_position(&self, s: f64, t: f64) -> (f64, f64) {
+        ((2. * s - 1.) * self.aspect_ratio, 1. - 2. * t)
+    }
+
+    /// Moves the plane of focus to the given distance along the view
+    /// direction, keeping the field of view.
+    fn set_focus_dist(&mut self, focus_dist: f64) {
+        let scale = focus_dist / self.focus_dist;
+        self.horizontal *= scale;
+        self.vertical *= scale;
+        self.upper_left_corner =
+            self.origin - self.horizontal / 2. + self.vertical / 2. - self.w * focus_dist;
+        self.focus_dist = focus_dist;
+    }
+}
+
+impl Camera for StandardCamera {
+    fn get_ray(&self, s: f64, t: f64, lens: (f64, f64), time: f64) -> Ray {
+        let time = self.shutter.0 + time * (self.shutter.1 - self.shutter.0);
+        match self.projection {
+            Projection::Perspective => {
+                let (x, y) = self.aperture_shape.sample(lens);
+                let offset = (self.u * x + self.v * y) * self.lens_radius;
+                Ray::new(
+                    self.origin + offset,
+                    (self.upper_left_corner + self.horizontal * s
+                        - self.vertical * t
+                        - self.origin
+                        - offset)
+                        .unit(),
+                    time,
+                )
+            }
+            Projection::Orthographic { .. } => Ray::new(
+                self.upper_left_corner + self.horizontal * s - self.vertical * t,
+                -self.w,
+                time,
+            ),
+            Projection::Equirectangular => {
+                let longitude = (s - 0.5) * consts::TAU;
+                let latitude = (0.5 - t) * consts::PI;
+                let direction = (self.u * longitude.sin() - self.w * longitude.cos())
+                    * latitude.cos()
+                    + self.v * latitude.sin();
+                Ray::new(self.origin, direction, time)
+            }
+            Projection::Fisheye { mapping, fov } => {
+                let (x, y) = self.fisheye_position(s, t);
+                let r = (x * x + y * y).sqrt();
+
+                let half_fov = fov.to_radians() / 2.;
+                let theta = match mapping {
+                    FisheyeMapping::Equidistant => r * half_fov,
+                    FisheyeMapping::Equisolid => {
+                        2. * (r * (half_fov / 2.).sin()).clamp(-1., 1.).asin()
+                    }
+                };
+
+                let radial = if r > 0. {
+                    (self.u * x + self.v * y) / r
+                } else {
+                    Vec3::zeros()
+                };
+                let direction = -self.w * theta.cos() + radial * theta.sin();
+                Ray::new(self.origin, direction, time)
+            }
+            Projection::CubeMap => {
+                let column = ((s * 3.) as usize).min(2);
+                let row = ((t * 2.) as usize).min(1);
+                let a = (s * 3. - column as f64) * 2. - 1.;
+                let b = 1. - (t * 2. - row as f64) * 2.;
+
+                let (face_forward, face_right, face_up) = self.cube_faces()[row * 3 + column];
+                let direction = face_forward + face_right * a + face_up * b;
+                Ray::new(self.origin, direction, time)
+            }
+        }
+    }
+
+    fn get_weighted_ray(&self, s: f64, t: f64, lens: (f64, f64), time: f64) -> Option<(Ray, f64)> {
+        // Fisheye lenses only cover the image circle, leaving the corners black
+        if let Projection::Fisheye { .. } = self.projection {
+            let (x, y) = self.fisheye_position(s, t);
+            if x * x + y * y > 1. {
+                return None;
+            }
+        }
+        Some((self.get_ray(s, t, lens, time), 1.))
+    }
+
+    fn project(&self, point: &Point3) -> Option<FilmPoint> {
+        let d = *point - self.origin;
+        let film_point = |q: Point3, depth: f64| {
+            let offset = q - self.upper_left_corner;
+            Some(FilmPoint {
+                s: offset.dot(self.horizontal) / self.horizontal.length_squared(),
+                t: -offset.dot(self.vertical) / self.vertical.length_squared(),
+                depth,
+            })
+        };
+
+        match self.projection {
+            Projection::Perspective => {
+                let depth = -d.dot(self.w);
+                if depth == 0. {
+                    return None;
+                }
+                film_point(self.origin + d * (self.focus_dist / depth), depth)
+            }
+            Projection::Orthographic { .. } => film_point(*point, -d.dot(self.w)),
+            Projection::Equirectangular => {
+                let dir = d.unit();
+                let longitude = dir.dot(self.u).atan2(-dir.dot(self.w));
+                let latitude = dir.dot(self.v).clamp(-1., 1.).asin();
+                Some(FilmPoint {
+                    s: longitude / consts::TAU + 0.5,
+                    t: 0.5 - latitude / consts::PI,
+                    depth: d.length(),
+                })
+            }
+            Projection::Fisheye { mapping, fov } => {
+                let dir = d.unit();
+                let theta = (-dir.dot(self.w)).clamp(-1., 1.).acos();
+                let half_fov = fov.to_radians() / 2.;
+                let r = match mapping {
+                    FisheyeMapping::Equidistant => theta / half_fov,
+                    FisheyeMapping::Equisolid => (theta / 2.).sin() / (half_fov / 2.).sin(),
+                };
+                let phi = dir.dot(self.v).atan2(dir.dot(self.u));
+                let (x, y) = (r * phi.cos(), r * phi.sin());
+                Some(FilmPoint {
+                    s: (x / self.aspect_ratio + 1.) / 2.,
+                    t: (1. - y) / 2.,
+                    depth: d.length(),
+                })
+            }
+            Projection::CubeMap => {
+                let faces = self.cube_faces();
+                let face =
+                    (0..6).max_by(|&i, &j| d.dot(faces[i].0).total_cmp(&d.dot(faces[j].0)))?;
+                let (face_forward, face_right, face_up) = faces[face];
+                let a = d.dot(face_right) / d.dot(face_forward);
+                let b = d.dot(face_up) / d.dot(face_forward);
+                Some(FilmPoint {
+                    s: ((face % 3) as f64 + (a + 1.) / 2.) / 3.,
+                    t: ((face / 3) as f64 + (1. - b) / 2.) / 2.,
+                    depth: d.length(),
+                })
+            }
+        }
+    }
+
+    fn focus(&mut self, world: &dyn Hittable, rng: &mut rngs::SmallRng) {
+        let (s, t) = match (self.projection, self.autofocus) {
+            (Projection::Perspective, Some(point)) => point,
+            _ => return,
+        };
+
+        // Trace through the centre of the lens
+        let direction =
+            (self.upper_left_corner + self.horizontal * s - self.vertical * t - self.origin).unit();
+        let r = Ray::new(self.origin, direction, self.shutter.0);
+        if let Some(hitrec) = world.hit(&r, &Interval::new(0.001, f64::INFINITY), rng) {
+            self.set_focus_dist(hitrec.t * direction.dot(-self.w));
+        }
+    }
+
+    fn exposure(&self) -> f64 {
+        self.exposure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{
+        material::Lambertian,
+        object::{HittableList, Sphere},
+        vec3::Vec3,
+        Colour, Point3,
+    };
+
+    use super::{Camera, CameraBuilder, FisheyeMapping};
+
+    #[test]
+    fn equirectangular_covers_sphere() {
+        let camera = CameraBuilder::new()
+            .origin(Point3::new(0., 0., 0.))
+            .look_dir(Vec3::new(0., 0., 1.))
+            .equirectangular()
+            .build();
+
+        let forward = camera.get_ray(0.5, 0.5, (0.5, 0.5), 0.);
+        let behind = camera.get_ray(0., 0.5, (0.5, 0.5), 0.);
+        let right = camera.get_ray(0.75, 0.5, (0.5, 0.5), 0.);
+        let up = camera.get_ray(0.5, 0., (0.5, 0.5), 0.);
+
+        assert!(forward.direction.is_close(&Vec3::new(0., 0., 1.)));
+        assert!(behind.direction.is_close(&Vec3::new(0., 0., -1.)));
+        assert!(right.direction.is_close(&Vec3::new(-1., 0., 0.)));
+        assert!(up.direction.is_close(&Vec3::new(0., 1., 0.)));
+    }
+
+    #[test]
+    fn fisheye_edge_matches_fov() {
+        for mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid] {
+            let camera = CameraBuilder::new()
+                .origin(Point3::new(0., 0., 0.))
+                .look_dir(Vec3::new(0., 0., 1.))
+                .aspect_ratio(1.)
+                .fisheye(mapping, 180.)
+                .build();
+
+            let centre = camera.get_ray(0.5, 0.5, (0.5, 0.5), 0.);
+            let top = camera.get_ray(0.5, 0., (0.5, 0.5), 0.);
+
+            assert!(centre.direction.is_close(&Vec3::new(0., 0., 1.)));
+            assert!(top.direction.is_close(&Vec3::new(0., 1., 0.)));
+        }
+    }
+
+    #[test]
+    fn fisheye_rejects_corners() {
+        let camera = CameraBuilder::new()
+            .origin(Point3::new(0., 0., 0.))
+            .look_dir(Vec3::new(0., 0., 1.))
+            .aspect_ratio(16. / 9.)
+            .fisheye(FisheyeMapping::Equisolid, 180.)
+            .build();
+
+        assert!(camera.get_weighted_ray(0.5, 0.5, (0.5, 0.5), 0.).is_some());
+        assert!(camera.get_weighted_ray(0.5, 0., (0.5, 0.5), 0.).is_some());
+        assert!(camera.get_weighted_ray(0., 0., (0.5, 0.5), 0.).is_none());
+        assert!(camera.get_weighted_ray(0.9, 0.5, (0.5, 0.5), 0.).is_none());
+    }
+
+    #[test]
+    fn cube_map_face_centres() {
+        let camera = CameraBuilder::new()
+            .origin(Point3::new(0., 0., 0.))
+            .look_dir(Vec3::new(0., 0., 1.))
+            .cube_map()
+            .build();
+
+        let expected = [
+            (0., 0., Vec3::new(-1., 0., 0.)),
+            (1., 0., Vec3::new(1., 0., 0.)),
+            (2., 0., Vec3::new(0., 1., 0.)),
+            (0., 1., Vec3::new(0., -1., 0.)),
+            (1., 1., Vec3::new(0., 0., 1.)),
+            (2., 1., Vec3::new(0., 0., -1.)),
+        ];
+        for (column, row, direction) in expected {
+            let r = camera.get_ray((column + 0.5) / 3., (row + 0.5) / 2., (0.5, 0.5), 0.);
+            assert!(r.direction.is_close(&direction), "{:?}", r.direction);
+        }
+    }
+
+    #[test]
+    fn autofocus_on_subject() {
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0., 0., -27.),
+            2.,
+            Arc::new(Lambertian::from_colour(Colour::new(0.5, 0.5, 0.5))),
+        )));
+
+        let mut camera = CameraBuilder::new()
+            .origin(Point3::new(0., 0., 0.))
+            .look_at(Point3::new(0., 0., -1.))
+            .aperture(0.5)
+            .autofocus()
+            .build();
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        camera.focus(&world, &mut rng);
+
+        assert!((camera.focus_dist - 25.).abs() < 1e-9);
+        assert!((camera.upper_left_corner.z + 25.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn physical_parameters() {
+        let mut builder = CameraBuilder::new();
+        builder
+            .sensor_size(36., 24.)
+            .focal_length(50.)
+            .f_number(2.)
+            .shutter_speed(1. / 100.)
+            .iso(100.)
+            .units_per_metre(100.);
+
+        assert!((builder.effective_fov() - 26.99).abs() < 0.01);
+        assert!((builder.effective_aperture() - 2.5).abs() < 1e-9);
+        assert_eq!(builder.effective_shutter(), (0., 0.01));
+        assert!((builder.exposure() - 1. / (1.2 * 400.)).abs() < 1e-12);
+
+        // Without an ISO the exposure is left alone
+        assert_eq!(CameraBuilder::new().f_number(8.).exposure(), 1.);
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let camera = CameraBuilder::new()
+            .origin(Point3::new(0., 10., 0.))
+            .look_at(Point3::new(0., 0., 0.))
+            .v_up(Vec3::new(0., 0., 1.))
+            .orthographic(4., 2.)
+            .build();
+
+        let top_left = camera.get_ray(0., 0., (0.5, 0.5), 0.);
+        let bottom_right = camera.get_ray(1., 1., (0.5, 0.5), 0.);
+
+        let down = Vec3::new(0., -1., 0.);
+        assert!(top_left.direction.is_close(&down));
+        assert!(bottom_right.direction.is_close(&down));
+        assert!(top_left.origin.is_close(&Point3::new(2., 10., 1.)));
+        assert!(bottom_right.origin.is_close(&Point3::new(-2., 10., -1.)));
+    }
+
+    #[test]
+    fn project_inverts_get_ray() {
+        let mut cameras = Vec::new();
+        for i in 0..5 {
+            let mut builder = CameraBuilder::new();
+            builder
+                .origin(Point3::new(1., 2., 3.))
+                .look_at(Point3::new(0., 0., 0.))
+                .aspect_ratio(2.);
+            match i {
+                0 => builder.aperture(0.5),
+                1 => builder.orthographic(4., 2.),
+                2 => builder.equirectangular(),
+                3 => builder.fisheye(FisheyeMapping::Equisolid, 180.),
+                _ => builder.cube_map(),
+            };
+            cameras.push(builder.build());
+        }
+
+        for camera in &cameras {
+            for (s, t) in [(0.5, 0.5), (0.4, 0.3), (0.6, 0.7), (0.45, 0.55)] {
+                let r = camera.get_ray(s, t, (0.5, 0.5), 0.);
+                let p = camera.project(&r.at(2.)).unwrap();
+                assert!((p.s - s).abs() < 1e-9, "{:?}", camera.projection);
+                assert!((p.t - t).abs() < 1e-9, "{:?}", camera.projection);
+                assert!(p.depth > 0.);
+            }
+        }
+    }
+}
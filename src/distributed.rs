@@ -0,0 +1,424 @@
+//! Distributes the rendering of a scene across multiple worker processes.
+//!
+//! A [`Coordinator`] splits the image into tiles and hands them out over TCP
+//! to any number of workers, each of which holds an identical copy of the
+//! scene and serves requests with a [`Worker`]. Workers reply with the linear
+//! (HDR) colour of every pixel in the tile, which the coordinator merges into
+//! the final image.
+//!
+//! The wire protocol is deliberately simple. On connecting, the worker sends
+//! the width and height of its image as two little-endian `u64` values, so
+//! the coordinator can check that both are rendering the same scene. A tile
+//! request is then four little-endian `u64` values (`x`, `y`, `width`,
+//! `height`), and the response is `width * height * 3` little-endian `f64`
+//! values in row-major order.
+
+use std::{
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Condvar, Mutex},
+    thread,
+};
+
+use crate::{
+    scene::{self, Scene, Tile},
+    Colour,
+};
+
+/// Serves tile requests from a coordinator using a local copy of the scene.
+pub struct Worker<'a> {
+    scene: &'a Scene,
+}
+
+impl<'a> Worker<'a> {
+    pub fn new(scene: &'a Scene) -> Self {
+        Self { scene }
+    }
+
+    /// Accepts coordinator connections forever, serving each connection on
+    /// its own thread.
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        thread::scope(|s| {
+            for stream in listener.incoming() {
+                let stream = stream?;
+                s.spawn(move || {
+                    if let Err(e) = self.serve_connection(stream) {
+                        eprintln!("worker connection failed: {}", e);
+                    }
+                });
+            }
+            Ok(())
+        })
+    }
+
+    /// Serves tile requests on a single connection until the coordinator
+    /// closes it.
+    pub fn serve_connection(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = BufWriter::new(stream);
+
+        for value in [self.scene.image_width(), self.scene.image_height()] {
+            writer.write_all(&(value as u64).to_le_bytes())?;
+        }
+        writer.flush()?;
+
+        while let Some(tile) = read_tile(&mut reader)? {
+            let inside = |start: usize, size: usize, limit: usize| {
+                start.checked_add(size).is_some_and(|end| end <= limit)
+            };
+            if !inside(tile.x, tile.width, self.scene.image_width())
+                || !inside(tile.y, tile.height, self.scene.image_height())
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("tile {:?} lies outside of the image", tile),
+                ));
+            }
+
+            for colour in self.scene.render_tile(&tile) {
+                for c in [colour.x, colour.y, colour.z] {
+                    writer.write_all(&c.to_le_bytes())?;
+                }
+            }
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The tiles still to be rendered, shared between the threads driving each
+/// worker.
+struct TileQueue {
+    remaining: Vec<Tile>,
+    /// The number of tiles currently being rendered, which are returned to
+    /// `remaining` if their worker fails.
+    in_flight: usize,
+}
+
+/// Hands out tiles of an image to a set of workers and merges the results.
+pub struct Coordinator {
+    workers: Vec<SocketAddr>,
+    image_width: usize,
+    image_height: usize,
+    tile_size: usize,
+}
+
+impl Coordinator {
+    pub fn new<A: ToSocketAddrs>(
+        workers: &[A],
+        image_width: usize,
+        image_height: usize,
+    ) -> io::Result<Self> {
+        let mut addrs = Vec::new();
+        for worker in workers {
+            addrs.extend(worker.to_socket_addrs()?);
+        }
+
+        Ok(Self {
+            workers: addrs,
+            image_width,
+            image_height,
+            tile_size: 32,
+        })
+    }
+
+    /// Sets the width and height of the square tiles sent to the workers.
+    pub fn tile_size(&mut self, tile_size: usize) -> &mut Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    /// Renders the image across all workers, returning the linear colour of
+    /// each pixel in row-major order.
+    ///
+    /// Tiles from a worker that fails are handed to the remaining workers, and
+    /// an error is only returned if every worker fails before the image is
+    /// complete.
+    pub fn render_hdr(&self) -> io::Result<Vec<Colour>> {
+        let mut tiles = Tile::split(self.image_width, self.image_height, self.tile_size);
+        tiles.reverse();
+        let queue = Mutex::new(TileQueue {
+            remaining: tiles,
+            in_flight: 0,
+        });
+        let returned = Condvar::new();
+        let image = Mutex::new(vec![Colour::zeros(); self.image_width * self.image_height]);
+
+        let errors: Vec<io::Error> = thread::scope(|s| {
+            let handles: Vec<_> = self
+                .workers
+                .iter()
+                .map(|addr| s.spawn(|| self.drive_worker(addr, &queue, &returned, &image)))
+                .collect();
+
+            handles
+                .into_iter()
+                .filter_map(|h| h.join().expect("worker thread panicked").err())
+                .collect()
+        });
+
+        let remaining = queue.into_inner().unwrap().remaining;
+        if !remaining.is_empty() {
+            let reason = errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(io::Error::other(format!(
+                "{} tiles could not be rendered by any worker: {}",
+                remaining.len(),
+                reason
+            )));
+        }
+
+        Ok(image.into_inner().unwrap())
+    }
+
+    /// Renders the image across all workers into a gamma corrected 8 bit RGB
+    /// buffer.
+    pub fn render(&self, pixel_buffer: &mut [u8]) -> io::Result<()> {
+        let image = self.render_hdr()?;
+        for (pixel, colour) in pixel_buffer.chunks_exact_mut(3).zip(image) {
+            let (r, g, b) = scene::to_rgb8(colour);
+            pixel.copy_from_slice(&[r, g, b]);
+        }
+        Ok(())
+    }
+
+    /// Hands tiles to one worker until none are left. While other workers
+    /// are still rendering, this waits rather than finishing, in case one of
+    /// them fails and its tile is returned.
+    fn drive_worker(
+        &self,
+        addr: &SocketAddr,
+        queue: &Mutex<TileQueue>,
+        returned: &Condvar,
+        image: &Mutex<Vec<Colour>>,
+    ) -> io::Result<()> {
+        let with_addr = |e: io::Error| io::Error::new(e.kind(), format!("{}: {}", addr, e));
+        let stream = TcpStream::connect(addr).map_err(with_addr)?;
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = BufWriter::new(stream);
+        self.check_dimensions(&mut reader).map_err(with_addr)?;
+
+        loop {
+            let tile = {
+                let mut queue = queue.lock().unwrap();
+                loop {
+                    if let Some(tile) = queue.remaining.pop() {
+                        queue.in_flight += 1;
+                        break tile;
+                    }
+                    if queue.in_flight == 0 {
+                        return Ok(());
+                    }
+                    queue = returned.wait(queue).unwrap();
+                }
+            };
+
+            let result = request_tile(&mut reader, &mut writer, &tile);
+            if let Ok(colours) = &result {
+                let mut image = image.lock().unwrap();
+                for (i, row) in colours.chunks_exact(tile.width).enumerate() {
+                    let offset = (tile.y + i) * self.image_width + tile.x;
+                    image[offset..offset + tile.width].copy_from_slice(row);
+                }
+            }
+
+            let mut queue = queue.lock().unwrap();
+            queue.in_flight -= 1;
+            if result.is_err() {
+                // Give the tile back so another worker can pick it up
+                queue.remaining.push(tile);
+            }
+            returned.notify_all();
+            result.map_err(with_addr)?;
+        }
+    }
+
+    /// Reads the image size sent by a worker when it connects, and checks
+    /// that it matches the image being rendered.
+    fn check_dimensions(&self, reader: &mut impl Read) -> io::Result<()> {
+        let mut buf = [0; 16];
+        reader.read_exact(&mut buf)?;
+        let width = u64::from_le_bytes(buf[..8].try_into().unwrap());
+        let height = u64::from_le_bytes(buf[8..].try_into().unwrap());
+        if (width, height) != (self.image_width as u64, self.image_height as u64) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "worker renders a {}x{} image, but expected {}x{}",
+                    width, height, self.image_width, self.image_height
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn request_tile(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    tile: &Tile,
+) -> io::Result<Vec<Colour>> {
+    for value in [tile.x, tile.y, tile.width, tile.height] {
+        writer.write_all(&(value as u64).to_le_bytes())?;
+    }
+    writer.flush()?;
+
+    let mut colours = Vec::with_capacity(tile.pixel_count());
+    let mut buf = [0; 24];
+    for _ in 0..tile.pixel_count() {
+        reader.read_exact(&mut buf)?;
+        colours.push(Colour::new(
+            f64::from_le_bytes(buf[0..8].try_into().unwrap()),
+            f64::from_le_bytes(buf[8..16].try_into().unwrap()),
+            f64::from_le_bytes(buf[16..24].try_into().unwrap()),
+        ));
+    }
+    Ok(colours)
+}
+
+/// Reads a tile request, returning `None` if the connection was closed
+/// cleanly before a new request started.
+fn read_tile(reader: &mut impl Read) -> io::Result<Option<Tile>> {
+    let mut buf = [0; 32];
+    match reader.read_exact(&mut buf[..8]) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    reader.read_exact(&mut buf[8..])?;
+
+    let mut values = [0; 4];
+    for (value, bytes) in values.iter_mut().zip(buf.chunks_exact(8)) {
+        *value = usize::try_from(u64::from_le_bytes(bytes.try_into().unwrap()))
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "tile is too large"))?;
+    }
+    let [x, y, width, height] = values;
+    Ok(Some(Tile::new(x, y, width, height)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+        time::Duration,
+    };
+
+    use crate::{camera::CameraBuilder, object::HittableList, scene::Scene, Colour};
+
+    use super::{Coordinator, Worker};
+
+    fn empty_scene(background: Colour) -> Scene {
+        Scene::new(
+            HittableList::new(),
+            CameraBuilder::new().build(),
+            5,
+            1,
+            10,
+            7,
+            background,
+        )
+    }
+
+    #[test]
+    fn render_across_local_workers() {
+        let background = Colour::new(0.25, 0.5, 1.);
+        let scene = empty_scene(background);
+
+        let listeners: Vec<TcpListener> = (0..2)
+            .map(|_| TcpListener::bind("127.0.0.1:0").unwrap())
+            .collect();
+        let addrs: Vec<_> = listeners.iter().map(|l| l.local_addr().unwrap()).collect();
+
+        let image = thread::scope(|s| {
+            for listener in listeners {
+                let scene = &scene;
+                s.spawn(move || {
+                    let (stream, _) = listener.accept().unwrap();
+                    Worker::new(scene).serve_connection(stream).unwrap();
+                });
+            }
+
+            Coordinator::new(&addrs, 10, 7)
+                .unwrap()
+                .tile_size(3)
+                .render_hdr()
+                .unwrap()
+        });
+
+        assert_eq!(image.len(), 70);
+        assert!(image.iter().all(|c| *c == background));
+    }
+
+    #[test]
+    fn reassign_tiles_from_failed_worker() {
+        let background = Colour::new(0.25, 0.5, 1.);
+        let scene = empty_scene(background);
+        let (healthy, failing) = (
+            TcpListener::bind("127.0.0.1:0").unwrap(),
+            TcpListener::bind("127.0.0.1:0").unwrap(),
+        );
+        let addrs = [healthy.local_addr().unwrap(), failing.local_addr().unwrap()];
+
+        let image = thread::scope(|s| {
+            let scene = &scene;
+            s.spawn(move || {
+                let (stream, _) = healthy.accept().unwrap();
+                Worker::new(scene).serve_connection(stream).unwrap();
+            });
+            // Takes a tile, then drops the connection after the healthy worker
+            // has run out of tiles
+            s.spawn(move || {
+                let (mut stream, _) = failing.accept().unwrap();
+                for value in [10u64, 7] {
+                    stream.write_all(&value.to_le_bytes()).unwrap();
+                }
+                let _ = stream.read_exact(&mut [0; 32]);
+                thread::sleep(Duration::from_millis(200));
+            });
+
+            Coordinator::new(&addrs, 10, 7)
+                .unwrap()
+                .tile_size(3)
+                .render_hdr()
+                .unwrap()
+        });
+
+        assert!(image.iter().all(|c| *c == background));
+    }
+
+    #[test]
+    fn reject_worker_with_other_image_size() {
+        let scene = empty_scene(Colour::zeros());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let result = thread::scope(|s| {
+            let scene = &scene;
+            s.spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                Worker::new(scene).serve_connection(stream).unwrap();
+            });
+            Coordinator::new(&[addr], 4, 4).unwrap().render_hdr()
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn fail_without_workers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        assert!(Coordinator::new(&[addr], 4, 4)
+            .unwrap()
+            .render_hdr()
+            .is_err());
+    }
+}
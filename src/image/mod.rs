@@ -1,2 +1,5 @@
 pub mod png;
 pub mod ppm;
+pub mod sink;
+
+pub use sink::ImageSink;
@@ -1,7 +1,9 @@
 use std::fs::File;
-use std::io::{self, BufWriter};
+use std::io::{self, BufWriter, Write};
 use std::path::Path;
 
+use super::ImageSink;
+
 pub fn write_image<P: AsRef<Path>, const WIDTH: usize, const HEIGHT: usize>(
     pixels: &[u8],
     path: P,
@@ -25,3 +27,81 @@ pub fn write_image<P: AsRef<Path>, const WIDTH: usize, const HEIGHT: usize>(
 
     Ok(())
 }
+
+/// Streams rows of an image into a PNG encoder, so that the whole image never
+/// needs to be held in memory.
+pub struct PngSink<W: Write + 'static> {
+    writer: Option<png::StreamWriter<'static, W>>,
+}
+
+impl PngSink<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P, width: usize, height: usize) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Self::new(BufWriter::new(file), width, height)
+    }
+}
+
+impl<W: Write + 'static> PngSink<W> {
+    pub fn new(w: W, width: usize, height: usize) -> io::Result<Self> {
+        let mut encoder = png::Encoder::new(
+            w,
+            width.try_into().expect("Width cannot be larger than u32"),
+            height.try_into().expect("Height cannot be larger than u32"),
+        );
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let writer = encoder.write_header()?.into_stream_writer()?;
+
+        Ok(Self {
+            writer: Some(writer),
+        })
+    }
+}
+
+impl<W: Write + 'static> ImageSink for PngSink<W> {
+    fn write_rows(&mut self, pixels: &[u8]) -> io::Result<()> {
+        match &mut self.writer {
+            Some(writer) => writer.write_all(pixels),
+            None => Err(io::Error::other("png sink has already been finished")),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match self.writer.take() {
+            Some(writer) => Ok(writer.finish()?),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+
+    use crate::image::ImageSink;
+
+    use super::PngSink;
+
+    #[test]
+    fn stream_rows() {
+        let (width, height) = (5, 4);
+        let pixels: Vec<u8> = (0..width * height * 3).map(|i| i as u8).collect();
+
+        let path =
+            std::env::temp_dir().join(format!("lumiere-stream-rows-{}.png", std::process::id()));
+        let mut sink = PngSink::create(&path, width, height).unwrap();
+        for rows in pixels.chunks(width * 3 * 3) {
+            sink.write_rows(rows).unwrap();
+        }
+        sink.finish().unwrap();
+        drop(sink);
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut decoded).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(decoded, pixels);
+    }
+}
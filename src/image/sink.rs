@@ -0,0 +1,21 @@
+use std::io;
+
+/// A destination for rendered pixels that receives the image a few rows at a
+/// time, from top to bottom, as each bucket of rows finishes rendering.
+pub trait ImageSink {
+    /// Writes the next rows of gamma corrected 8 bit RGB values. The slice
+    /// always contains a whole number of rows.
+    fn write_rows(&mut self, pixels: &[u8]) -> io::Result<()>;
+
+    /// Called once all rows of the image have been written.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl ImageSink for Vec<u8> {
+    fn write_rows(&mut self, pixels: &[u8]) -> io::Result<()> {
+        self.extend_from_slice(pixels);
+        Ok(())
+    }
+}
@@ -5,12 +5,17 @@
 pub mod aabb;
 pub mod bvh;
 pub mod camera;
+pub mod distributed;
 pub mod image;
 pub mod interval;
+pub mod light_group;
+pub mod loader;
 pub mod material;
+pub mod matrix;
 pub mod object;
 pub mod ray;
 pub mod scene;
+pub mod stats;
 pub mod texture;
 pub mod vec3;
 
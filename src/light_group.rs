@@ -0,0 +1,115 @@
+//! Splits the rendered image into additive layers, one per light group.
+//!
+//! Emissive materials are tagged with a light group name (see
+//! [`DiffuseLight::with_group`](crate::material::DiffuseLight::with_group)),
+//! and any light that is not tagged belongs to [`DEFAULT_LIGHT_GROUP`]. Light
+//! from the scene background is kept in its own layer, so that summing every
+//! layer reproduces the beauty pass.
+
+use std::collections::BTreeMap;
+
+use crate::{scene, Colour};
+
+/// The light group of emitters that have not been given a group.
+pub const DEFAULT_LIGHT_GROUP: &str = "default";
+
+/// Where the light carried along a path originated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LightSource<'a> {
+    Background,
+    Light(&'a str),
+}
+
+/// Linear colour layers for each light group, in row-major order.
+#[derive(Debug, Clone)]
+pub struct LightGroupLayers {
+    pub width: usize,
+    pub height: usize,
+    /// The light from each light group, keyed by the group name.
+    pub groups: BTreeMap<String, Vec<Colour>>,
+    /// The light from the scene background.
+    pub background: Vec<Colour>,
+}
+
+impl LightGroupLayers {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            groups: BTreeMap::new(),
+            background: vec![Colour::zeros(); width * height],
+        }
+    }
+
+    /// Sums all of the layers back into the beauty pass.
+    pub fn beauty(&self) -> Vec<Colour> {
+        let mut beauty = self.background.clone();
+        for layer in self.groups.values() {
+            for (pixel, colour) in beauty.iter_mut().zip(layer) {
+                *pixel += *colour;
+            }
+        }
+        beauty
+    }
+
+    /// Converts a layer into gamma corrected 8 bit RGB values, ready to be
+    /// written to an image.
+    pub fn to_rgb8(layer: &[Colour]) -> Vec<u8> {
+        layer
+            .iter()
+            .flat_map(|colour| {
+                let (r, g, b) = scene::to_rgb8(*colour);
+                [r, g, b]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{camera::CameraBuilder, material, object, scene::Scene, Colour, Point3};
+
+    #[test]
+    fn layers_sum_to_beauty() {
+        let mut world = object::HittableList::new();
+        world.add(Arc::new(object::Sphere::new(
+            Point3::new(-1., 0., 0.),
+            0.8,
+            Arc::new(
+                material::DiffuseLight::from_colour(Colour::new(1., 0., 0.)).with_group("left"),
+            ),
+        )));
+        world.add(Arc::new(object::Sphere::new(
+            Point3::new(1., 0., 0.),
+            0.8,
+            Arc::new(material::DiffuseLight::from_colour(Colour::new(0., 0., 1.))),
+        )));
+        let camera = CameraBuilder::new()
+            .origin(Point3::new(0., 0., -5.))
+            .look_at(Point3::new(0., 0., 0.))
+            .build();
+        let background = Colour::new(0., 0.5, 0.);
+        let scene = Scene::new(world, camera, 5, 4, 16, 9, background);
+
+        let layers = scene.render_light_groups();
+
+        assert_eq!(
+            layers.groups.keys().collect::<Vec<_>>(),
+            vec![super::DEFAULT_LIGHT_GROUP, "left"]
+        );
+        let left = &layers.groups["left"];
+        let right = &layers.groups[super::DEFAULT_LIGHT_GROUP];
+        for (i, beauty) in layers.beauty().iter().enumerate() {
+            // Each layer only holds light of its own colour
+            assert_eq!(left[i].y + left[i].z, 0.);
+            assert_eq!(right[i].x + right[i].y, 0.);
+            assert_eq!(layers.background[i].x + layers.background[i].z, 0.);
+
+            let sum = left[i] + right[i] + layers.background[i];
+            assert!(sum.is_close(beauty));
+            assert!((beauty.x + beauty.y * 2. + beauty.z - 1.).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,525 @@
+//! glTF 2.0 scenes, from either `.gltf` or binary `.glb` files.
+//!
+//! Node transforms are baked into the mesh vertices, and PBR
+//! metallic-roughness materials are approximated with the closest material
+//! this renderer supports. Punctual lights become small emissive spheres.
+//! Anything that can't be represented is skipped with a warning rather than
+//! failing the import.
+
+use std::{path::Path, sync::Arc};
+
+use gltf::{camera::Projection, image::Format, khr_lights_punctual::Kind, mesh::Mode};
+
+use crate::{
+    camera::CameraBuilder,
+    material::{DiffuseLight, Lambertian, Material, Metal},
+    matrix::Matrix4,
+    object::{HittableList, Sphere, TriangleMesh},
+    scene::Scene,
+    texture::{ImageTexture, Texture},
+    vec3::Vec3,
+    Colour, Point3,
+};
+
+use super::{LoadError, Model, ModelObject};
+
+/// The radius of the spheres standing in for punctual lights.
+pub const LIGHT_RADIUS: f64 = 0.05;
+
+/// The contents of an imported glTF scene.
+#[derive(Default)]
+pub struct GltfScene {
+    pub model: Model,
+    /// The punctual lights, as emissive spheres.
+    pub lights: Vec<Arc<Sphere>>,
+    /// A camera for each camera node, in the order they were found. The
+    /// aspect ratio is only set if the file specifies one.
+    pub cameras: Vec<CameraBuilder>,
+    /// Everything in the file that was ignored or approximated.
+    pub warnings: Vec<String>,
+}
+
+impl GltfScene {
+    /// Collects the meshes and lights into a list for adding to a scene.
+    pub fn to_list(&self) -> HittableList {
+        let mut list = self.model.to_list();
+        for light in &self.lights {
+            list.add(light.clone());
+        }
+        list
+    }
+
+    /// Renders the scene through its first camera, or a default camera if
+    /// it has none, with the aspect ratio matching the image.
+    pub fn into_scene(
+        mut self,
+        max_depth: usize,
+        samples_per_pixel: usize,
+        image_width: usize,
+        image_height: usize,
+        background: Colour,
+    ) -> Scene {
+        let world = self.to_list();
+        let mut camera = if self.cameras.is_empty() {
+            CameraBuilder::new()
+        } else {
+            self.cameras.swap_remove(0)
+        };
+        camera.aspect_ratio(image_width as f64 / image_height as f64);
+
+        Scene::new(
+            world,
+            camera.build(),
+            max_depth,
+            samples_per_pixel,
+            image_width,
+            image_height,
+            background,
+        )
+    }
+}
+
+/// Loads a glTF or GLB file along with any buffers and images it references,
+/// which are found relative to the file. Primitives without a material use
+/// `default_material`.
+pub fn load_gltf<P: AsRef<Path>>(
+    path: P,
+    default_material: Arc<dyn Material>,
+) -> Result<GltfScene, LoadError> {
+    let (document, buffers, images) = gltf::import(path)?;
+    Ok(Importer::new(&buffers, &images, default_material).import(&document))
+}
+
+/// Parses a glTF or GLB file from memory. Buffers and images must be
+/// embedded in the file.
+pub fn parse_gltf(
+    bytes: &[u8],
+    default_material: Arc<dyn Material>,
+) -> Result<GltfScene, LoadError> {
+    let (document, buffers, images) = gltf::import_slice(bytes)?;
+    Ok(Importer::new(&buffers, &images, default_material).import(&document))
+}
+
+struct Importer<'a> {
+    buffers: &'a [gltf::buffer::Data],
+    images: &'a [gltf::image::Data],
+    default_material: Arc<dyn Material>,
+    materials: Vec<Option<Arc<dyn Material>>>,
+    textures: Vec<Option<Arc<ImageTexture>>>,
+    scene: GltfScene,
+}
+
+impl<'a> Importer<'a> {
+    fn new(
+        buffers: &'a [gltf::buffer::Data],
+        images: &'a [gltf::image::Data],
+        default_material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            buffers,
+            images,
+            default_material,
+            materials: Vec::new(),
+            textures: Vec::new(),
+            scene: GltfScene::default(),
+        }
+    }
+
+    fn import(mut self, document: &gltf::Document) -> GltfScene {
+        self.materials = vec![None; document.materials().len()];
+        self.textures = vec![None; self.images.len()];
+
+        for extension in document.extensions_used() {
+            if extension != "KHR_lights_punctual" {
+                self.warn(format!("extension {} is not supported", extension));
+            }
+        }
+
+        match document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            Some(scene) => {
+                for node in scene.nodes() {
+                    self.node(&node, &Matrix4::IDENTITY);
+                }
+            }
+            None => self.warn("file has no scenes".to_string()),
+        }
+        self.scene
+    }
+
+    /// Records a warning, ignoring repeats.
+    fn warn(&mut self, warning: String) {
+        if !self.scene.warnings.contains(&warning) {
+            self.scene.warnings.push(warning);
+        }
+    }
+
+    fn node(&mut self, node: &gltf::Node, parent: &Matrix4) {
+        let local = Matrix4::from_columns(node.transform().matrix().map(|c| c.map(f64::from)));
+        let transform = *parent * local;
+
+        if node.skin().is_some() {
+            self.warn("skinning is not supported, meshes are left in their bind pose".to_string());
+        }
+        if let Some(mesh) = node.mesh() {
+            self.mesh(&mesh, &transform);
+        }
+        if let Some(camera) = node.camera() {
+            self.camera(&camera, &transform);
+        }
+        if let Some(light) = node.light() {
+            self.light(&light, &transform);
+        }
+        for child in node.children() {
+            self.node(&child, &transform);
+        }
+    }
+
+    fn mesh(&mut self, mesh: &gltf::Mesh, transform: &Matrix4) {
+        let name = mesh
+            .name()
+            .map_or_else(|| format!("mesh{}", mesh.index()), str::to_string);
+        let primitive_count = mesh.primitives().len();
+        let inverse = transform.inverse();
+
+        for primitive in mesh.primitives() {
+            if primitive.mode() != Mode::Triangles {
+                self.warn(format!(
+                    "{}: primitive mode {:?} is not supported",
+                    name,
+                    primitive.mode()
+                ));
+                continue;
+            }
+            if primitive.morph_targets().len() > 0 {
+                self.warn(format!("{}: morph targets are not supported", name));
+            }
+
+            let buffers = self.buffers;
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let Some(positions) = reader.read_positions() else {
+                self.warn(format!("{}: primitive has no positions", name));
+                continue;
+            };
+            let positions: Vec<Point3> = positions
+                .map(|p| transform.transform_point(vec3(p)))
+                .collect();
+
+            let vertices: Vec<usize> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+                None => (0..positions.len()).collect(),
+            };
+            if vertices.iter().any(|&i| i >= positions.len()) {
+                self.warn(format!("{}: vertex index out of range", name));
+                continue;
+            }
+            let indices: Vec<[usize; 3]> = vertices
+                .chunks_exact(3)
+                .map(|t| [t[0], t[1], t[2]])
+                .collect();
+            if indices.is_empty() {
+                continue;
+            }
+
+            let vertex_count = positions.len();
+            let material = self.material(&primitive.material());
+            let mut triangles = TriangleMesh::new(positions, indices, material);
+            // A singular transform flattens the mesh, leaving nothing to
+            // smooth
+            if let (Some(normals), Some(inverse)) = (reader.read_normals(), &inverse) {
+                let normals: Vec<Vec3> = normals
+                    .map(|n| Matrix4::transform_normal(inverse, vec3(n)))
+                    .collect();
+                if normals.len() == vertex_count {
+                    triangles = triangles.with_normals(normals);
+                } else {
+                    self.warn(format!(
+                        "{}: ignoring normals that don't match the positions",
+                        name
+                    ));
+                }
+            }
+            if let Some(uvs) = reader.read_tex_coords(0) {
+                // glTF puts the origin of texture space at the top left
+                let uvs: Vec<(f64, f64)> = uvs
+                    .into_f32()
+                    .map(|[u, v]| (u as f64, 1. - v as f64))
+                    .collect();
+                if uvs.len() == vertex_count {
+                    triangles = triangles.with_uvs(uvs);
+                } else {
+                    self.warn(format!(
+                        "{}: ignoring texture coordinates that don't match the positions",
+                        name
+                    ));
+                }
+            }
+
+            let name = if primitive_count > 1 {
+                format!("{}.{}", name, primitive.index())
+            } else {
+                name.clone()
+            };
+            self.scene.model.objects.push(ModelObject {
+                name,
+                mesh: Arc::new(triangles),
+            });
+        }
+    }
+
+    fn material(&mut self, material: &gltf::Material) -> Arc<dyn Material> {
+        let Some(index) = material.index() else {
+            return self.default_material.clone();
+        };
+        if let Some(converted) = &self.materials[index] {
+            return converted.clone();
+        }
+
+        let converted = self.convert_material(material);
+        self.materials[index] = Some(converted.clone());
+        converted
+    }
+
+    fn convert_material(&mut self, material: &gltf::Material) -> Arc<dyn Material> {
+        let name = material.name().map_or_else(
+            || format!("material{}", material.index().unwrap()),
+            str::to_string,
+        );
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, _] = pbr.base_color_factor().map(f64::from);
+        let base_colour = Colour::new(r, g, b);
+
+        if material.alpha_mode() != gltf::material::AlphaMode::Opaque {
+            self.warn(format!("{}: transparency is not supported", name));
+        }
+        if material.normal_texture().is_some() || material.occlusion_texture().is_some() {
+            self.warn(format!("{}: normal and occlusion maps are ignored", name));
+        }
+        if pbr.metallic_roughness_texture().is_some() {
+            self.warn(format!("{}: metallic-roughness maps are ignored", name));
+        }
+
+        let emission = vec3(material.emissive_factor());
+        if emission != Colour::zeros() {
+            if material.emissive_texture().is_some() {
+                self.warn(format!("{}: emissive maps are ignored", name));
+            }
+            return Arc::new(DiffuseLight::from_colour(emission));
+        }
+
+        if pbr.metallic_factor() >= 0.5 {
+            if pbr.base_color_texture().is_some() {
+                self.warn(format!("{}: base colour maps on metals are ignored", name));
+            }
+            return Arc::new(Metal::new(base_colour, pbr.roughness_factor() as f64));
+        }
+
+        let texture = pbr
+            .base_color_texture()
+            .and_then(|info| self.texture(&name, &info));
+        match texture {
+            Some(texture) => {
+                if base_colour != Colour::new(1., 1., 1.) {
+                    self.warn(format!(
+                        "{}: base colour factor is ignored when textured",
+                        name
+                    ));
+                }
+                Arc::new(Lambertian::new(texture))
+            }
+            None => Arc::new(Lambertian::from_colour(base_colour)),
+        }
+    }
+
+    fn texture(&mut self, name: &str, info: &gltf::texture::Info) -> Option<Arc<dyn Texture>> {
+        if info.tex_coord() != 0 {
+            self.warn(format!(
+                "{}: only the first texture coordinate set is used",
+                name
+            ));
+        }
+        let index = info.texture().source().index();
+        if let Some(texture) = &self.textures[index] {
+            return Some(texture.clone());
+        }
+
+        let image = &self.images[index];
+        let channels = match image.format {
+            Format::R8 => 1,
+            Format::R8G8 => 2,
+            Format::R8G8B8 => 3,
+            Format::R8G8B8A8 => 4,
+            format => {
+                self.warn(format!(
+                    "{}: image format {:?} is not supported",
+                    name, format
+                ));
+                return None;
+            }
+        };
+        let texture = Arc::new(ImageTexture::from_pixels(
+            image.width as usize,
+            image.height as usize,
+            channels,
+            image.pixels.clone(),
+        ));
+        self.textures[index] = Some(texture.clone());
+        Some(texture)
+    }
+
+    fn camera(&mut self, camera: &gltf::Camera, transform: &Matrix4) {
+        let mut builder = CameraBuilder::new();
+        // Cameras look down their local -z axis with +y up
+        builder
+            .origin(transform.transform_point(Point3::zeros()))
+            .look_dir(transform.transform_vector(Vec3::new(0., 0., -1.)))
+            .v_up(transform.transform_vector(Vec3::new(0., 1., 0.)));
+
+        match camera.projection() {
+            Projection::Perspective(perspective) => {
+                builder.fov((perspective.yfov() as f64).to_degrees());
+                if let Some(aspect_ratio) = perspective.aspect_ratio() {
+                    builder.aspect_ratio(aspect_ratio as f64);
+                }
+            }
+            Projection::Orthographic(orthographic) => {
+                let (x_mag, y_mag) = (orthographic.xmag() as f64, orthographic.ymag() as f64);
+                builder
+                    .orthographic(2. * x_mag, 2. * y_mag)
+                    .aspect_ratio(x_mag / y_mag);
+            }
+        }
+        self.scene.cameras.push(builder);
+    }
+
+    fn light(&mut self, light: &gltf::khr_lights_punctual::Light, transform: &Matrix4) {
+        let name = light
+            .name()
+            .map_or_else(|| format!("light{}", light.index()), str::to_string);
+        match light.kind() {
+            Kind::Directional => {
+                self.warn(format!("{}: directional lights are not supported", name));
+                return;
+            }
+            Kind::Spot { .. } => {
+                self.warn(format!("{}: spot light cones are ignored", name));
+            }
+            Kind::Point => {}
+        }
+        if light.range().is_some() {
+            self.warn(format!("{}: light range is ignored", name));
+        }
+
+        // A sphere of radiance L has an intensity of L times its projected
+        // area, matching the light's intensity in candela
+        let colour = vec3(light.color());
+        let area = std::f64::consts::PI * LIGHT_RADIUS * LIGHT_RADIUS;
+        let radiance = colour * (light.intensity() as f64 / area);
+        let material = DiffuseLight::from_colour(radiance).with_group(&name);
+
+        self.scene.lights.push(Arc::new(Sphere::new(
+            transform.transform_point(Point3::zeros()),
+            LIGHT_RADIUS,
+            Arc::new(material),
+        )));
+    }
+}
+
+fn vec3([x, y, z]: [f32; 3]) -> Vec3 {
+    Vec3::new(x as f64, y as f64, z as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{interval, material, object::Hittable, ray::Ray, vec3::Vec3, Colour, Point3};
+
+    use super::parse_gltf;
+
+    /// A triangle moved 5 units away, a camera inside a transformed parent
+    /// node, and a point and directional light.
+    const SCENE: &str = r#"{
+        "asset": {"version": "2.0"},
+        "extensionsUsed": ["KHR_lights_punctual"],
+        "extensions": {"KHR_lights_punctual": {"lights": [
+            {"type": "point", "name": "lamp", "intensity": 2},
+            {"type": "directional"}
+        ]}},
+        "scene": 0,
+        "scenes": [{"nodes": [0, 2, 3, 4]}],
+        "nodes": [
+            {"mesh": 0, "translation": [0, 0, -5]},
+            {"camera": 0, "translation": [0, 1, 0]},
+            {"children": [1], "translation": [0, 0, 2]},
+            {"extensions": {"KHR_lights_punctual": {"light": 0}}, "translation": [0, 3, 0]},
+            {"extensions": {"KHR_lights_punctual": {"light": 1}}}
+        ],
+        "cameras": [{"type": "perspective", "perspective": {"yfov": 0.5, "znear": 0.1}}],
+        "materials": [{"pbrMetallicRoughness": {"baseColorFactor": [0.2, 0.4, 0.6, 1], "metallicFactor": 0}}],
+        "meshes": [{"name": "tri", "primitives": [{"attributes": {"POSITION": 0}, "indices": 1, "material": 0}]}],
+        "buffers": [{"byteLength": 44, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA="}],
+        "bufferViews": [
+            {"buffer": 0, "byteOffset": 0, "byteLength": 36},
+            {"buffer": 0, "byteOffset": 36, "byteLength": 6}
+        ],
+        "accessors": [
+            {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]},
+            {"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}
+        ]
+    }"#;
+
+    #[test]
+    fn import_scene() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let mut scene = parse_gltf(SCENE.as_bytes(), mat).unwrap();
+        assert_eq!(scene.model.triangle_count(), 1);
+        assert!(scene.model.get("tri").is_some());
+        assert_eq!(scene.cameras.len(), 1);
+        assert_eq!(scene.lights.len(), 1);
+        assert_eq!(
+            scene.warnings,
+            vec!["light1: directional lights are not supported"]
+        );
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let world = scene.to_list();
+        let r = Ray::new(Point3::new(0.25, 0.25, 0.), Vec3::new(0., 0., -1.), 0.);
+        let hit = world.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!(hit.point.is_close(&Point3::new(0.25, 0.25, -5.)));
+
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 1., 0.), 0.);
+        let hit = world.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - (3. - super::LIGHT_RADIUS)).abs() < 1e-9);
+
+        let camera = scene.cameras[0].build();
+        let film = crate::camera::Camera::project(&camera, &Point3::new(0., 1., -5.)).unwrap();
+        assert!((film.s - 0.5).abs() < 1e-9 && (film.t - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn warn_about_mismatched_attributes() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        // Only two normals for the three positions
+        let scene = SCENE
+            .replace(r#""POSITION": 0"#, r#""POSITION": 0, "NORMAL": 2"#)
+            .replace(
+                r#"{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}"#,
+                r#"{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"},
+                {"bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3"}"#,
+            );
+        let scene = parse_gltf(scene.as_bytes(), mat).unwrap();
+        assert_eq!(scene.model.triangle_count(), 1);
+        assert!(scene
+            .warnings
+            .contains(&"tri: ignoring normals that don't match the positions".to_string()));
+    }
+}
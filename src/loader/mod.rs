@@ -0,0 +1,130 @@
+//! Loaders for models stored in common 3D file formats.
+
+pub mod gltf;
+pub mod obj;
+pub mod ply;
+pub mod stl;
+
+use std::{error::Error, fmt, io, sync::Arc};
+
+use crate::object::{HittableList, TriangleMesh};
+
+pub use self::gltf::{load_gltf, GltfScene};
+pub use obj::load_obj;
+pub use ply::load_ply;
+pub use stl::load_stl;
+
+/// A named part of a loaded model.
+#[derive(Debug)]
+pub struct ModelObject {
+    pub name: String,
+    pub mesh: Arc<TriangleMesh>,
+}
+
+/// The meshes loaded from a model file, in the order they appear in the
+/// file.
+#[derive(Debug, Default)]
+pub struct Model {
+    pub objects: Vec<ModelObject>,
+}
+
+impl Model {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Finds the first object with the given name.
+    pub fn get(&self, name: &str) -> Option<&ModelObject> {
+        self.objects.iter().find(|o| o.name == name)
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.objects.iter().map(|o| o.mesh.triangle_count()).sum()
+    }
+
+    /// Collects every mesh in the model into a list for adding to a scene.
+    pub fn to_list(&self) -> HittableList {
+        let mut list = HittableList::new();
+        for object in &self.objects {
+            list.add(object.mesh.clone());
+        }
+        list
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// The file is malformed, with the line it was found on for text formats.
+    Parse {
+        line: Option<usize>,
+        message: String,
+    },
+    /// A texture referenced by the model couldn't be loaded.
+    Texture(png::DecodingError),
+    /// A glTF file or one of its buffers or images couldn't be loaded.
+    Gltf(::gltf::Error),
+}
+
+impl LoadError {
+    pub(crate) fn invalid(message: impl Into<String>) -> Self {
+        LoadError::Parse {
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn parse(line: usize, message: impl Into<String>) -> Self {
+        LoadError::Parse {
+            line: Some(line),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::Parse {
+                line: Some(line),
+                message,
+            } => write!(f, "line {}: {}", line, message),
+            LoadError::Parse {
+                line: None,
+                message,
+            } => write!(f, "{}", message),
+            LoadError::Texture(e) => write!(f, "failed to load texture: {}", e),
+            LoadError::Gltf(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Parse { .. } => None,
+            LoadError::Texture(e) => Some(e),
+            LoadError::Gltf(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<png::DecodingError> for LoadError {
+    fn from(e: png::DecodingError) -> Self {
+        LoadError::Texture(e)
+    }
+}
+
+impl From<::gltf::Error> for LoadError {
+    fn from(e: ::gltf::Error) -> Self {
+        LoadError::Gltf(e)
+    }
+}
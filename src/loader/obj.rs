@@ -0,0 +1,442 @@
+//! Wavefront OBJ models and their MTL material libraries.
+//!
+//! Faces are triangulated as fans, and each object or group is split into a
+//! separate mesh for every material it uses. MTL materials are approximated
+//! with the closest material this renderer supports.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    str::SplitWhitespace,
+    sync::Arc,
+};
+
+use crate::{
+    material::{Dielectric, DiffuseLight, Lambertian, Material, Metal},
+    object::TriangleMesh,
+    texture::ImageTexture,
+    vec3::Vec3,
+    Colour, Point3,
+};
+
+use super::{LoadError, Model, ModelObject};
+
+/// Loads an OBJ file along with any material libraries it references, which
+/// are found relative to the OBJ file. Faces without a material use
+/// `default_material`.
+pub fn load_obj<P: AsRef<Path>>(
+    path: P,
+    default_material: Arc<dyn Material>,
+) -> Result<Model, LoadError> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    parse_obj(BufReader::new(File::open(path)?), dir, default_material)
+}
+
+/// Parses an OBJ model, loading material libraries relative to `dir`.
+pub fn parse_obj(
+    reader: impl BufRead,
+    dir: &Path,
+    default_material: Arc<dyn Material>,
+) -> Result<Model, LoadError> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut materials = HashMap::new();
+
+    let mut meshes: Vec<MeshBuilder> = Vec::new();
+    let mut mesh_indices = HashMap::new();
+    let mut object = String::from("default");
+    let mut group: Option<String> = None;
+    let mut material: Option<String> = None;
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = number + 1;
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("v") => positions.push(parse_vec3(&mut tokens, number)?),
+            Some("vn") => normals.push(parse_vec3(&mut tokens, number)?),
+            Some("vt") => {
+                let u = parse_f64(tokens.next(), number)?;
+                let v = tokens
+                    .next()
+                    .map_or(Ok(0.), |v| parse_f64(Some(v), number))?;
+                uvs.push((u, v));
+            }
+            Some("o") => {
+                object = rest_of_line(tokens, "default");
+                group = None;
+            }
+            Some("g") => group = Some(rest_of_line(tokens, "default")),
+            Some("usemtl") => {
+                // Names may contain spaces, as with `newmtl`
+                let name = rest_of_line(tokens, "");
+                material = (!name.is_empty()).then_some(name);
+            }
+            Some("mtllib") => {
+                for library in tokens {
+                    materials.extend(load_mtl(dir.join(library))?);
+                }
+            }
+            Some("f") => {
+                let corners = tokens
+                    .map(|t| parse_corner(t, number, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if corners.len() < 3 {
+                    return Err(LoadError::parse(number, "face has fewer than 3 vertices"));
+                }
+
+                let name = group.as_ref().unwrap_or(&object).clone();
+                let key = (name.clone(), material.clone());
+                let index = *mesh_indices.entry(key).or_insert_with(|| {
+                    let mat = material
+                        .as_ref()
+                        .and_then(|m| materials.get(m))
+                        .unwrap_or(&default_material);
+                    meshes.push(MeshBuilder::new(name, mat.clone()));
+                    meshes.len() - 1
+                });
+
+                let mesh = &mut meshes[index];
+                let corners: Vec<usize> = corners
+                    .into_iter()
+                    .map(|c| mesh.vertex(c, &positions, &uvs, &normals))
+                    .collect();
+                for i in 1..corners.len() - 1 {
+                    mesh.indices.push([corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+            // Smoothing groups, comments and anything unsupported
+            _ => {}
+        }
+    }
+
+    Ok(Model {
+        objects: meshes.into_iter().map(MeshBuilder::build).collect(),
+    })
+}
+
+/// Indices of the position, texture coordinate and normal of a face corner.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+/// Collects the vertices and triangles of one mesh, sharing vertices between
+/// faces that use the same combination of attributes.
+struct MeshBuilder {
+    name: String,
+    material: Arc<dyn Material>,
+    positions: Vec<Point3>,
+    uvs: Vec<Option<(f64, f64)>>,
+    normals: Vec<Option<Vec3>>,
+    indices: Vec<[usize; 3]>,
+    vertices: HashMap<Corner, usize>,
+}
+
+impl MeshBuilder {
+    fn new(name: String, material: Arc<dyn Material>) -> Self {
+        Self {
+            name,
+            material,
+            positions: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            indices: Vec::new(),
+            vertices: HashMap::new(),
+        }
+    }
+
+    fn vertex(
+        &mut self,
+        corner: Corner,
+        positions: &[Point3],
+        uvs: &[(f64, f64)],
+        normals: &[Vec3],
+    ) -> usize {
+        *self.vertices.entry(corner).or_insert_with(|| {
+            let (p, uv, n) = corner;
+            self.positions.push(positions[p]);
+            self.uvs.push(uv.map(|i| uvs[i]));
+            self.normals.push(n.map(|i| normals[i]));
+            self.positions.len() - 1
+        })
+    }
+
+    /// Builds the mesh, only keeping normals and texture coordinates if every
+    /// vertex has them.
+    fn build(self) -> ModelObject {
+        let mut mesh = TriangleMesh::new(self.positions, self.indices, self.material);
+        if let Some(uvs) = self.uvs.into_iter().collect() {
+            mesh = mesh.with_uvs(uvs);
+        }
+        if let Some(normals) = self.normals.into_iter().collect() {
+            mesh = mesh.with_normals(normals);
+        }
+        ModelObject {
+            name: self.name,
+            mesh: Arc::new(mesh),
+        }
+    }
+}
+
+fn rest_of_line(tokens: SplitWhitespace, default: &str) -> String {
+    let name = tokens.collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        default.to_string()
+    } else {
+        name
+    }
+}
+
+fn parse_f64(token: Option<&str>, line: usize) -> Result<f64, LoadError> {
+    let token = token.ok_or_else(|| LoadError::parse(line, "missing value"))?;
+    token
+        .parse()
+        .map_err(|_| LoadError::parse(line, format!("invalid number {}", token)))
+}
+
+fn parse_vec3(tokens: &mut SplitWhitespace, line: usize) -> Result<Vec3, LoadError> {
+    Ok(Vec3::new(
+        parse_f64(tokens.next(), line)?,
+        parse_f64(tokens.next(), line)?,
+        parse_f64(tokens.next(), line)?,
+    ))
+}
+
+/// Resolves a one-based or negative relative OBJ index.
+fn parse_index(token: &str, line: usize, count: usize) -> Result<usize, LoadError> {
+    let index: isize = token
+        .parse()
+        .map_err(|_| LoadError::parse(line, format!("invalid index {}", token)))?;
+    let resolved = match index {
+        i if i > 0 => i - 1,
+        i if i < 0 => count as isize + i,
+        _ => -1,
+    };
+    if resolved < 0 || resolved as usize >= count {
+        return Err(LoadError::parse(
+            line,
+            format!("index {} out of range", token),
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+/// Parses a face corner in the form `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+fn parse_corner(
+    token: &str,
+    line: usize,
+    positions: usize,
+    uvs: usize,
+    normals: usize,
+) -> Result<Corner, LoadError> {
+    let mut parts = token.split('/');
+    let position = parse_index(parts.next().unwrap_or(""), line, positions)?;
+    let uv = match parts.next() {
+        Some(uv) if !uv.is_empty() => Some(parse_index(uv, line, uvs)?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(n) if !n.is_empty() => Some(parse_index(n, line, normals)?),
+        _ => None,
+    };
+    Ok((position, uv, normal))
+}
+
+/// The properties of an MTL material that are used to pick a material.
+#[derive(Debug, Default)]
+struct MtlMaterial {
+    diffuse: Option<Colour>,
+    specular: Option<Colour>,
+    emission: Option<Colour>,
+    shininess: Option<f64>,
+    ior: Option<f64>,
+    dissolve: Option<f64>,
+    illum: Option<u32>,
+    diffuse_map: Option<String>,
+}
+
+impl MtlMaterial {
+    fn into_material(self, dir: &Path) -> Result<Arc<dyn Material>, LoadError> {
+        let black = Colour::zeros();
+        let emission = self.emission.unwrap_or(black);
+        let diffuse = self.diffuse.unwrap_or(Colour::new(0.8, 0.8, 0.8));
+        let specular = self.specular.unwrap_or(black);
+
+        if emission != black {
+            return Ok(Arc::new(DiffuseLight::from_colour(emission)));
+        }
+
+        // Glass uses one of the refraction illumination models, or is
+        // partially transparent
+        let transparent = self.dissolve.is_some_and(|d| d < 1.);
+        if matches!(self.illum, Some(4 | 6 | 7 | 9)) || transparent {
+            return Ok(Arc::new(Dielectric::new(self.ior.unwrap_or(1.5))));
+        }
+
+        // Mirrors use the reflection illumination models, or are purely
+        // specular
+        if matches!(self.illum, Some(3 | 5 | 8)) || (diffuse == black && specular != black) {
+            let fuzz = (2. / (self.shininess.unwrap_or(1000.) + 2.)).sqrt();
+            return Ok(Arc::new(Metal::new(specular, fuzz)));
+        }
+
+        Ok(match self.diffuse_map {
+            Some(map) => Arc::new(Lambertian::new(Arc::new(ImageTexture::open(
+                dir.join(map),
+            )?))),
+            None => Arc::new(Lambertian::from_colour(diffuse)),
+        })
+    }
+}
+
+/// Loads the materials in an MTL library, keyed by name.
+pub fn load_mtl<P: AsRef<Path>>(path: P) -> Result<HashMap<String, Arc<dyn Material>>, LoadError> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut parsed: Vec<(String, MtlMaterial)> = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let number = number + 1;
+        let mut tokens = line.split_whitespace();
+
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        if keyword == "newmtl" {
+            parsed.push((rest_of_line(tokens, "default"), MtlMaterial::default()));
+            continue;
+        }
+        let current = match parsed.last_mut() {
+            Some((_, current)) => current,
+            None => continue,
+        };
+
+        match keyword {
+            "Kd" => current.diffuse = Some(parse_vec3(&mut tokens, number)?),
+            "Ks" => current.specular = Some(parse_vec3(&mut tokens, number)?),
+            "Ke" => current.emission = Some(parse_vec3(&mut tokens, number)?),
+            "Ns" => current.shininess = Some(parse_f64(tokens.next(), number)?),
+            "Ni" => current.ior = Some(parse_f64(tokens.next(), number)?),
+            "d" => current.dissolve = Some(parse_f64(tokens.next(), number)?),
+            "Tr" => current.dissolve = Some(1. - parse_f64(tokens.next(), number)?),
+            "illum" => {
+                current.illum = Some(
+                    tokens
+                        .next()
+                        .and_then(|t| t.parse().ok())
+                        .ok_or_else(|| LoadError::parse(number, "invalid illumination model"))?,
+                )
+            }
+            // Options such as `-s` come before the file name
+            "map_Kd" => current.diffuse_map = tokens.last().map(str::to_string),
+            _ => {}
+        }
+    }
+
+    parsed
+        .into_iter()
+        .map(|(name, mtl)| Ok((name, mtl.into_material(dir)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Cursor, path::Path, sync::Arc};
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{interval, material, object::Hittable, ray::Ray, vec3::Vec3, Colour, Point3};
+
+    use super::{load_obj, parse_obj};
+
+    #[test]
+    fn parse_groups_and_faces() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let obj = "
+            # A quad and a triangle in separate groups
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0
+            v 0 1 0
+            vt 0 0
+            vt 1 0
+            vt 1 1
+            vt 0 1
+            vn 0 0 1
+            g quad
+            f 1/1/1 2/2/1 3/3/1 4/4/1
+            g triangle
+            f -4//1 -3//1 -2//1
+        ";
+        let model = parse_obj(Cursor::new(obj), Path::new(""), mat).unwrap();
+
+        assert_eq!(model.objects.len(), 2);
+        assert_eq!(model.get("quad").unwrap().mesh.triangle_count(), 2);
+        assert_eq!(model.get("triangle").unwrap().mesh.triangle_count(), 1);
+        assert_eq!(model.triangle_count(), 3);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0.25, 0.75, 1.), Vec3::new(0., 0., -1.), 0.);
+        let quad = &model.get("quad").unwrap().mesh;
+        let hit = quad.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.u - 0.25).abs() < 1e-12);
+        assert!((hit.v - 0.75).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reject_bad_indices() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let obj = "v 0 0 0\nv 1 0 0\nf 1 2 3\n";
+        assert!(parse_obj(Cursor::new(obj), Path::new(""), mat).is_err());
+    }
+
+    #[test]
+    fn load_materials() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let dir = std::env::temp_dir().join(format!("lumiere-obj-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("scene.mtl"),
+            "newmtl light\nKe 4 4 4\nnewmtl glass\nNi 1.5\nd 0.1\nnewmtl red\nKd 0.8 0.1 0.1\n\
+             newmtl warm light\nKe 2 1 1\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("scene.obj"),
+            "mtllib scene.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\n\
+             o lamp\nusemtl light\nf 1 2 3\n\
+             o window\nusemtl glass\nf 1 2 3\n\
+             o wall\nusemtl red\nf 1 2 3\nusemtl missing\nf 3 2 1\n\
+             o heater\nusemtl warm light\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        let model = load_obj(dir.join("scene.obj"), mat).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<_> = model.objects.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, ["lamp", "window", "wall", "wall", "heater"]);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0.2, 0.2, 1.), Vec3::new(0., 0., -1.), 0.);
+        let hit = model.objects[0].mesh.hit(&r, &interval::UNIVERSE, &mut rng);
+        let emitted = hit.unwrap().mat.emitted(0., 0., &Point3::zeros());
+        assert_eq!(emitted, Colour::new(4., 4., 4.));
+
+        let hit = model.objects[4].mesh.hit(&r, &interval::UNIVERSE, &mut rng);
+        let emitted = hit.unwrap().mat.emitted(0., 0., &Point3::zeros());
+        assert_eq!(emitted, Colour::new(2., 1., 1.));
+    }
+}
@@ -0,0 +1,542 @@
+//! Stanford PLY meshes, in ASCII or binary.
+//!
+//! Vertex positions, normals, texture coordinates and colours are read from
+//! the `vertex` element, and polygons from the `face` element are
+//! triangulated as fans. Other elements are skipped.
+
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{
+    material::{Lambertian, Material},
+    object::TriangleMesh,
+    texture::VertexColourTexture,
+    vec3::Vec3,
+    Colour, Point3,
+};
+
+use super::LoadError;
+
+/// The most elements to reserve space for up front. Counts in the header are
+/// untrusted, so larger elements grow as they are read.
+const MAX_RESERVED_ELEMENTS: usize = 1 << 16;
+
+/// Loads a PLY file. Meshes with vertex colours are shaded with a diffuse
+/// material using those colours, and the rest use `material`.
+pub fn load_ply<P: AsRef<Path>>(
+    path: P,
+    material: Arc<dyn Material>,
+) -> Result<Arc<TriangleMesh>, LoadError> {
+    parse_ply(BufReader::new(File::open(path)?), material)
+}
+
+/// Parses a PLY mesh. Meshes with vertex colours are shaded with a diffuse
+/// material using those colours, and the rest use `material`.
+pub fn parse_ply(
+    mut reader: impl BufRead,
+    material: Arc<dyn Material>,
+) -> Result<Arc<TriangleMesh>, LoadError> {
+    let header = Header::parse(&mut reader)?;
+    let mut body = match header.format {
+        Format::Ascii => Body::Ascii {
+            reader: Box::new(reader),
+            tokens: VecDeque::new(),
+            line: header.lines,
+        },
+        Format::Binary { big_endian } => Body::Binary {
+            reader: Box::new(reader),
+            big_endian,
+        },
+    };
+
+    let mut vertices = None;
+    let mut faces = None;
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => vertices = Some(read_vertices(element, &mut body)?),
+            "face" => faces = Some(read_faces(element, &mut body)?),
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        property.skip(&mut body)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let vertices = vertices.ok_or_else(|| LoadError::invalid("PLY file has no vertices"))?;
+    let faces = faces.ok_or_else(|| LoadError::invalid("PLY file has no faces"))?;
+    if faces
+        .iter()
+        .flatten()
+        .any(|&i| i >= vertices.positions.len())
+    {
+        return Err(LoadError::invalid("face vertex index out of range"));
+    }
+
+    let material = match vertices.colours {
+        Some(_) => Arc::new(Lambertian::new(Arc::new(VertexColourTexture::new()))),
+        None => material,
+    };
+    let mut mesh = TriangleMesh::new(vertices.positions, faces, material);
+    if let Some(normals) = vertices.normals {
+        mesh = mesh.with_normals(normals);
+    }
+    if let Some(uvs) = vertices.uvs {
+        mesh = mesh.with_uvs(uvs);
+    }
+    if let Some(colours) = vertices.colours {
+        mesh = mesh.with_colours(colours);
+    }
+    Ok(Arc::new(mesh))
+}
+
+enum Format {
+    Ascii,
+    Binary { big_endian: bool },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Type {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl Type {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Type::Int8,
+            "uchar" | "uint8" => Type::UInt8,
+            "short" | "int16" => Type::Int16,
+            "ushort" | "uint16" => Type::UInt16,
+            "int" | "int32" => Type::Int32,
+            "uint" | "uint32" => Type::UInt32,
+            "float" | "float32" => Type::Float32,
+            "double" | "float64" => Type::Float64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Type::Int8 | Type::UInt8 => 1,
+            Type::Int16 | Type::UInt16 => 2,
+            Type::Int32 | Type::UInt32 | Type::Float32 => 4,
+            Type::Float64 => 8,
+        }
+    }
+
+    /// The value of full intensity for colours of this type.
+    fn colour_max(self) -> f64 {
+        match self {
+            Type::UInt8 => u8::MAX as f64,
+            Type::UInt16 => u16::MAX as f64,
+            Type::Int8 => i8::MAX as f64,
+            Type::Int16 => i16::MAX as f64,
+            Type::Int32 => i32::MAX as f64,
+            Type::UInt32 => u32::MAX as f64,
+            Type::Float32 | Type::Float64 => 1.,
+        }
+    }
+}
+
+struct Property {
+    name: String,
+    ty: Type,
+    /// The type of the length of a list property.
+    count: Option<Type>,
+}
+
+impl Property {
+    fn skip(&self, body: &mut Body) -> Result<(), LoadError> {
+        let len = match self.count {
+            Some(count) => body.read(count)? as usize,
+            None => 1,
+        };
+        for _ in 0..len {
+            body.read(self.ty)?;
+        }
+        Ok(())
+    }
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+    /// The number of lines in the header.
+    lines: usize,
+}
+
+impl Header {
+    fn parse(reader: &mut impl BufRead) -> Result<Self, LoadError> {
+        let mut format = None;
+        let mut elements: Vec<Element> = Vec::new();
+        let mut line = String::new();
+        let mut number = 0;
+
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(LoadError::invalid("PLY header has no end"));
+            }
+            number += 1;
+            let mut tokens = line.split_whitespace();
+            let keyword = tokens.next();
+            if number == 1 {
+                if keyword != Some("ply") {
+                    return Err(LoadError::parse(number, "not a PLY file"));
+                }
+                continue;
+            }
+
+            match keyword {
+                Some("format") => {
+                    format = Some(match tokens.next() {
+                        Some("ascii") => Format::Ascii,
+                        Some("binary_little_endian") => Format::Binary { big_endian: false },
+                        Some("binary_big_endian") => Format::Binary { big_endian: true },
+                        _ => return Err(LoadError::parse(number, "unknown format")),
+                    })
+                }
+                Some("element") => {
+                    let name = tokens.next();
+                    let count = tokens.next().and_then(|t| t.parse().ok());
+                    match (name, count) {
+                        (Some(name), Some(count)) => elements.push(Element {
+                            name: name.to_string(),
+                            count,
+                            properties: Vec::new(),
+                        }),
+                        _ => return Err(LoadError::parse(number, "invalid element")),
+                    }
+                }
+                Some("property") => {
+                    let element = elements
+                        .last_mut()
+                        .ok_or_else(|| LoadError::parse(number, "property outside an element"))?;
+                    let tokens: Vec<&str> = tokens.collect();
+                    let property =
+                        match tokens[..] {
+                            ["list", count, ty, name] => Type::parse(count)
+                                .zip(Type::parse(ty))
+                                .map(|(count, ty)| Property {
+                                    name: name.to_string(),
+                                    ty,
+                                    count: Some(count),
+                                }),
+                            [ty, name] => Type::parse(ty).map(|ty| Property {
+                                name: name.to_string(),
+                                ty,
+                                count: None,
+                            }),
+                            _ => None,
+                        };
+                    element.properties.push(
+                        property.ok_or_else(|| LoadError::parse(number, "invalid property"))?,
+                    );
+                }
+                Some("end_header") => break,
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            format: format.ok_or_else(|| LoadError::invalid("PLY header has no format"))?,
+            elements,
+            lines: number,
+        })
+    }
+}
+
+/// The data following the header, read one value at a time.
+enum Body<'a> {
+    Ascii {
+        reader: Box<dyn BufRead + 'a>,
+        tokens: VecDeque<String>,
+        line: usize,
+    },
+    Binary {
+        reader: Box<dyn Read + 'a>,
+        big_endian: bool,
+    },
+}
+
+impl Body<'_> {
+    fn read(&mut self, ty: Type) -> Result<f64, LoadError> {
+        match self {
+            Body::Ascii {
+                reader,
+                tokens,
+                line,
+            } => {
+                while tokens.is_empty() {
+                    let mut text = String::new();
+                    if reader.read_line(&mut text)? == 0 {
+                        return Err(LoadError::invalid("unexpected end of PLY file"));
+                    }
+                    *line += 1;
+                    tokens.extend(text.split_whitespace().map(str::to_string));
+                }
+                let token = tokens.pop_front().unwrap();
+                token
+                    .parse()
+                    .map_err(|_| LoadError::parse(*line, format!("invalid number {:?}", token)))
+            }
+            Body::Binary { reader, big_endian } => {
+                let mut bytes = [0; 8];
+                let bytes = &mut bytes[..ty.size()];
+                reader.read_exact(bytes)?;
+                if *big_endian {
+                    bytes.reverse();
+                }
+                Ok(match ty {
+                    Type::Int8 => bytes[0] as i8 as f64,
+                    Type::UInt8 => bytes[0] as f64,
+                    Type::Int16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    Type::UInt16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    Type::Int32 => i32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+                    Type::UInt32 => u32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+                    Type::Float32 => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+                    Type::Float64 => f64::from_le_bytes(bytes.try_into().unwrap()),
+                })
+            }
+        }
+    }
+}
+
+struct Vertices {
+    positions: Vec<Point3>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f64, f64)>>,
+    colours: Option<Vec<Colour>>,
+}
+
+fn read_vertices(element: &Element, body: &mut Body) -> Result<Vertices, LoadError> {
+    let find = |names: &[&str]| {
+        element
+            .properties
+            .iter()
+            .position(|p| p.count.is_none() && names.contains(&p.name.as_str()))
+    };
+    let all = |indices: [Option<usize>; 3]| match indices {
+        [Some(a), Some(b), Some(c)] => Some([a, b, c]),
+        _ => None,
+    };
+
+    let position = all([find(&["x"]), find(&["y"]), find(&["z"])])
+        .ok_or_else(|| LoadError::invalid("PLY vertices have no position"))?;
+    let normal = all([find(&["nx"]), find(&["ny"]), find(&["nz"])]);
+    let uv = find(&["u", "s", "texture_u", "texture_s"]).zip(find(&[
+        "v",
+        "t",
+        "texture_v",
+        "texture_t",
+    ]));
+    let colour = all([
+        find(&["red", "diffuse_red"]),
+        find(&["green", "diffuse_green"]),
+        find(&["blue", "diffuse_blue"]),
+    ]);
+
+    let reserved = element.count.min(MAX_RESERVED_ELEMENTS);
+    let mut vertices = Vertices {
+        positions: Vec::with_capacity(reserved),
+        normals: normal.map(|_| Vec::with_capacity(reserved)),
+        uvs: uv.map(|_| Vec::with_capacity(reserved)),
+        colours: colour.map(|_| Vec::with_capacity(reserved)),
+    };
+    let mut values = vec![0.; element.properties.len()];
+    for _ in 0..element.count {
+        for (value, property) in values.iter_mut().zip(&element.properties) {
+            match property.count {
+                Some(_) => property.skip(body)?,
+                None => *value = body.read(property.ty)?,
+            }
+        }
+
+        let vec3 = |[x, y, z]: [usize; 3]| Vec3::new(values[x], values[y], values[z]);
+        vertices.positions.push(vec3(position));
+        if let (Some(normals), Some(normal)) = (&mut vertices.normals, normal) {
+            normals.push(vec3(normal));
+        }
+        if let (Some(uvs), Some((u, v))) = (&mut vertices.uvs, uv) {
+            uvs.push((values[u], values[v]));
+        }
+        if let (Some(colours), Some(colour)) = (&mut vertices.colours, colour) {
+            let max = element.properties[colour[0]].ty.colour_max();
+            colours.push(vec3(colour) / max);
+        }
+    }
+    Ok(vertices)
+}
+
+fn read_faces(element: &Element, body: &mut Body) -> Result<Vec<[usize; 3]>, LoadError> {
+    let indices = element
+        .properties
+        .iter()
+        .position(|p| p.count.is_some() && (p.name == "vertex_indices" || p.name == "vertex_index"))
+        .ok_or_else(|| LoadError::invalid("PLY faces have no vertex indices"))?;
+
+    let mut triangles = Vec::with_capacity(element.count.min(MAX_RESERVED_ELEMENTS));
+    let mut polygon = Vec::new();
+    for _ in 0..element.count {
+        for (i, property) in element.properties.iter().enumerate() {
+            if i != indices {
+                property.skip(body)?;
+                continue;
+            }
+
+            let len = body.read(property.count.unwrap())? as usize;
+            polygon.clear();
+            for _ in 0..len {
+                let index = body.read(property.ty)?;
+                if index < 0. {
+                    return Err(LoadError::invalid("negative face vertex index"));
+                }
+                polygon.push(index as usize);
+            }
+            for i in 1..polygon.len().saturating_sub(1) {
+                triangles.push([polygon[0], polygon[i], polygon[i + 1]]);
+            }
+        }
+    }
+    Ok(triangles)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, sync::Arc};
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{
+        interval, material,
+        matrix::Matrix4,
+        object::{Hittable, Transform},
+        ray::Ray,
+        texture::{Texture, VertexColourTexture},
+        vec3::Vec3,
+        Colour, Point3,
+    };
+
+    use super::parse_ply;
+
+    #[test]
+    fn ascii_with_vertex_colours() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let ply = "ply
+format ascii 1.0
+comment A unit square with a red and a blue edge
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 0 255
+1 1 0 0 0 255
+0 1 0 255 0 0
+4 0 1 2 3
+";
+        let mesh = parse_ply(Cursor::new(ply), mat).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0.25, 0.5, 1.), Vec3::new(0., 0., -1.), 0.);
+        let hit = mesh.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!(hit.colour.unwrap().is_close(&Colour::new(0.75, 0., 0.25)));
+
+        // The colours follow the mesh when it's moved, and reach the
+        // material through its texture
+        let moved = Transform::new(mesh, Matrix4::translation(Vec3::new(10., 0., 0.)));
+        let r = Ray::new(Point3::new(10.75, 0.5, 1.), Vec3::new(0., 0., -1.), 0.);
+        let hit = moved.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        let colour = VertexColourTexture::new().value(&hit);
+        assert!(colour.is_close(&Colour::new(0.25, 0., 0.75)));
+    }
+
+    #[test]
+    fn binary_little_endian() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let mut ply = b"ply
+format binary_little_endian 1.0
+element vertex 3
+property float x
+property float y
+property float z
+element edge 1
+property int vertex1
+property int vertex2
+element face 1
+property list uchar uint vertex_indices
+end_header
+"
+        .to_vec();
+        for v in [[0f32, 0., -1.], [1., 0., -1.], [0., 1., -1.]] {
+            for x in v {
+                ply.extend(x.to_le_bytes());
+            }
+        }
+        ply.extend(0i32.to_le_bytes());
+        ply.extend(1i32.to_le_bytes());
+        ply.push(3);
+        for i in [0u32, 1, 2] {
+            ply.extend(i.to_le_bytes());
+        }
+
+        let mesh = parse_ply(Cursor::new(ply), mat).unwrap();
+        assert_eq!(mesh.triangle_count(), 1);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0.25, 0.25, 0.), Vec3::new(0., 0., -1.), 0.);
+        let hit = mesh.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 1.).abs() < 1e-12);
+        assert!(hit.colour.is_none());
+    }
+
+    #[test]
+    fn reject_bad_files() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let point_cloud = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\n\
+                           property float y\nproperty float z\nend_header\n0 0 0\n";
+        assert!(parse_ply(Cursor::new(point_cloud), mat.clone()).is_err());
+
+        let truncated = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\n\
+                         property float y\nproperty float z\nend_header\n0 0 0\n";
+        assert!(parse_ply(Cursor::new(truncated), mat.clone()).is_err());
+
+        // A header claiming far more vertices than could fit in memory
+        let huge = "ply\nformat ascii 1.0\nelement vertex 1000000000000000\nproperty float x\n\
+                    property float y\nproperty float z\nend_header\n0 0 0\n";
+        assert!(parse_ply(Cursor::new(huge), mat).is_err());
+    }
+}
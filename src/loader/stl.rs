@@ -0,0 +1,211 @@
+//! STL meshes, in ASCII or binary.
+//!
+//! STL files store each triangle with its own vertices, so the mesh is flat
+//! shaded. Binary files may carry a colour for each facet in the VisCAM
+//! format, with bit 15 of the attribute set and five bits per channel.
+
+use std::{fs::File, io::Read, path::Path, sync::Arc};
+
+use crate::{
+    material::{Lambertian, Material},
+    object::TriangleMesh,
+    texture::VertexColourTexture,
+    vec3::Vec3,
+    Colour, Point3,
+};
+
+use super::LoadError;
+
+/// The size of the header and triangle count of a binary STL file.
+const BINARY_HEADER: usize = 84;
+/// The size of each triangle in a binary STL file.
+const BINARY_TRIANGLE: usize = 50;
+
+/// Loads an STL file. Binary meshes with facet colours are shaded with a
+/// diffuse material using those colours, and the rest use `material`.
+pub fn load_stl<P: AsRef<Path>>(
+    path: P,
+    material: Arc<dyn Material>,
+) -> Result<Arc<TriangleMesh>, LoadError> {
+    parse_stl(File::open(path)?, material)
+}
+
+/// Parses an STL mesh. Binary meshes with facet colours are shaded with a
+/// diffuse material using those colours, and the rest use `material`.
+pub fn parse_stl(
+    mut reader: impl Read,
+    material: Arc<dyn Material>,
+) -> Result<Arc<TriangleMesh>, LoadError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    // Binary files can also start with "solid", so check whether the size
+    // matches the triangle count first
+    let binary_count = bytes
+        .get(80..BINARY_HEADER)
+        .map(|count| u32::from_le_bytes(count.try_into().unwrap()) as usize);
+    let (positions, colours) = match binary_count {
+        Some(count) if bytes.len() == BINARY_HEADER + count * BINARY_TRIANGLE => {
+            parse_binary(&bytes[BINARY_HEADER..], count)
+        }
+        _ if bytes.starts_with(b"solid") => {
+            let text = std::str::from_utf8(&bytes)
+                .map_err(|_| LoadError::invalid("ASCII STL file isn't valid UTF-8"))?;
+            (parse_ascii(text)?, None)
+        }
+        _ => return Err(LoadError::invalid("not an STL file")),
+    };
+
+    let indices = (0..positions.len() / 3)
+        .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+        .collect();
+    Ok(Arc::new(match colours {
+        Some(colours) => {
+            let material = Arc::new(Lambertian::new(Arc::new(VertexColourTexture::new())));
+            TriangleMesh::new(positions, indices, material).with_colours(colours)
+        }
+        None => TriangleMesh::new(positions, indices, material),
+    }))
+}
+
+/// Reads the vertices of each triangle, and their colours if any facet has
+/// one. Facets without a colour are a light grey.
+fn parse_binary(bytes: &[u8], count: usize) -> (Vec<Point3>, Option<Vec<Colour>>) {
+    let float = |b: &[u8]| f32::from_le_bytes(b.try_into().unwrap()) as f64;
+    let default_colour = Colour::new(0.8, 0.8, 0.8);
+
+    let mut positions = Vec::with_capacity(3 * count);
+    let mut colours = Vec::with_capacity(3 * count);
+    let mut coloured = false;
+    for triangle in bytes.chunks_exact(BINARY_TRIANGLE) {
+        // Skip the facet normal
+        for vertex in triangle[12..48].chunks_exact(12) {
+            positions.push(Point3::new(
+                float(&vertex[0..4]),
+                float(&vertex[4..8]),
+                float(&vertex[8..12]),
+            ));
+        }
+
+        let attribute = u16::from_le_bytes([triangle[48], triangle[49]]);
+        let colour = if attribute & 0x8000 != 0 {
+            coloured = true;
+            let channel = |shift: u16| ((attribute >> shift) & 0x1f) as f64 / 31.;
+            Colour::new(channel(10), channel(5), channel(0))
+        } else {
+            default_colour
+        };
+        colours.extend([colour; 3]);
+    }
+    (positions, coloured.then_some(colours))
+}
+
+/// Reads the vertices of each facet, triangulating any with more than three
+/// as fans.
+fn parse_ascii(text: &str) -> Result<Vec<Point3>, LoadError> {
+    let mut positions = Vec::new();
+    let mut facet: Option<Vec<Point3>> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("facet") => facet = Some(Vec::new()),
+            Some("vertex") => {
+                let vertices = facet
+                    .as_mut()
+                    .ok_or_else(|| LoadError::parse(number, "vertex outside a facet"))?;
+                let mut coordinate = || {
+                    tokens
+                        .next()
+                        .and_then(|t| t.parse().ok())
+                        .ok_or_else(|| LoadError::parse(number, "invalid vertex"))
+                };
+                vertices.push(Vec3::new(coordinate()?, coordinate()?, coordinate()?));
+            }
+            Some("endfacet") => {
+                let vertices = facet
+                    .take()
+                    .ok_or_else(|| LoadError::parse(number, "endfacet outside a facet"))?;
+                for i in 1..vertices.len().saturating_sub(1) {
+                    positions.extend([vertices[0], vertices[i], vertices[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, sync::Arc};
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{interval, material, object::Hittable, ray::Ray, vec3::Vec3, Colour, Point3};
+
+    use super::parse_stl;
+
+    #[test]
+    fn ascii() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let stl = "solid square
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid square
+";
+        let mesh = parse_stl(Cursor::new(stl), mat).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0.25, 0.75, 1.), Vec3::new(0., 0., -1.), 0.);
+        let hit = mesh.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!(hit.point.is_close(&Point3::new(0.25, 0.75, 0.)));
+    }
+
+    #[test]
+    fn binary_with_facet_colours() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        // The header starts with "solid", as some exporters write
+        let mut stl = b"solid".to_vec();
+        stl.resize(80, 0);
+        stl.extend(1u32.to_le_bytes());
+        for x in [0f32, 0., 1., 0., 0., 0., 1., 0., 0., 0., 1., 0.] {
+            stl.extend(x.to_le_bytes());
+        }
+        // Pure red
+        stl.extend((0x8000u16 | 0x1f << 10).to_le_bytes());
+
+        let mesh = parse_stl(Cursor::new(stl), mat).unwrap();
+        assert_eq!(mesh.triangle_count(), 1);
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0.2, 0.2, 1.), Vec3::new(0., 0., -1.), 0.);
+        let hit = mesh.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!(hit.colour.unwrap().is_close(&Colour::new(1., 0., 0.)));
+    }
+
+    #[test]
+    fn reject_unknown_files() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        assert!(parse_stl(Cursor::new(b"not a mesh".to_vec()), mat).is_err());
+    }
+}
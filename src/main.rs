@@ -1,24 +1,72 @@
-use std::{error::Error, path::Path, sync::Arc};
+use std::{env, error::Error, net::TcpListener, path::Path, sync::Arc};
 
 use lumiere::{
-    bvh::BVHNode, camera, image, material, object, scene::Scene, texture, Colour, Point3,
+    bvh::BVHNode,
+    camera,
+    distributed::{Coordinator, Worker},
+    image, material, object,
+    scene::Scene,
+    texture, Colour, Point3,
 };
 use rand::{rngs, SeedableRng};
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut rng = rngs::SmallRng::from_rng(rand::thread_rng()).unwrap();
+// Image parameters
+const ASPECT_RATIO: f64 = 16. / 9.;
+const IMAGE_WIDTH: usize = 400;
+const IMAGE_HEIGHT: usize = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as usize;
 
-    // Image parameters
-    const ASPECT_RATIO: f64 = 16. / 9.;
-    const IMAGE_WIDTH: usize = 400;
-    const IMAGE_HEIGHT: usize = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as usize;
-    let samples_per_pixel: usize = 2000;
-    let max_depth = 50;
+/// Renders the scene locally by default. The same scene can also be rendered
+/// across multiple processes by starting workers with `lumiere worker <addr>`
+/// and then running `lumiere coordinator <addr>...` with each worker's
+/// address.
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
 
     // Pixel array as height * rows * channels 8 bit values
     const BUFFER_LENGTH: usize = 3 * IMAGE_WIDTH * IMAGE_HEIGHT;
     let mut pixels = vec![0_u8; BUFFER_LENGTH];
 
+    match args.first().map(String::as_str) {
+        None => {
+            // Render the scene to a frame buffer
+            let mut scene = build_scene();
+            scene.collect_stats(true);
+            scene.render(&mut pixels)?;
+            if let Some(stats) = scene.stats() {
+                eprint!("{}", stats);
+            }
+        }
+        Some("worker") => {
+            let addr = args.get(1).map_or("127.0.0.1:7878", String::as_str);
+            let listener = TcpListener::bind(addr)?;
+            eprintln!("Worker listening on {}", listener.local_addr()?);
+            let scene = build_scene();
+            return Ok(Worker::new(&scene).serve(listener)?);
+        }
+        Some("coordinator") => {
+            if args.len() < 2 {
+                return Err("coordinator requires at least one worker address".into());
+            }
+            Coordinator::new(&args[1..], IMAGE_WIDTH, IMAGE_HEIGHT)?.render(&mut pixels)?;
+        }
+        Some(mode) => {
+            return Err(format!("unknown mode {}, expected worker or coordinator", mode).into());
+        }
+    }
+
+    // Write the frame buffer to a file
+    image::png::write_image::<&Path, IMAGE_WIDTH, IMAGE_HEIGHT>(&pixels, Path::new("image.png"))?;
+    eprintln!("Saved image");
+
+    Ok(())
+}
+
+fn build_scene() -> Scene {
+    let mut rng = rngs::SmallRng::from_rng(rand::thread_rng()).unwrap();
+
+    let samples_per_pixel: usize = 2000;
+    let max_depth = 50;
+
     // Generate the objects
 
     // Camera
@@ -29,7 +77,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         .fov(20.)
         .aspect_ratio(ASPECT_RATIO)
         .aperture(0.)
-        .focus_dist(10.)
+        .autofocus()
         .build();
 
     // World
@@ -50,7 +98,8 @@ fn main() -> Result<(), Box<dyn Error>> {
         noise_texture,
     )));
 
-    let diff_light = Arc::new(material::DiffuseLight::from_colour(Colour::new(4., 4., 4.)));
+    let diff_light =
+        Arc::new(material::DiffuseLight::from_colour(Colour::new(4., 4., 4.)).with_group("quad"));
     world.add(Arc::new(object::Quad::new(
         Point3::new(3., 1., -2.),
         Point3::new(2., 0., 0.),
@@ -58,7 +107,8 @@ fn main() -> Result<(), Box<dyn Error>> {
         diff_light,
     )));
 
-    let diff_light = Arc::new(material::DiffuseLight::from_colour(Colour::new(4., 4., 4.)));
+    let diff_light =
+        Arc::new(material::DiffuseLight::from_colour(Colour::new(4., 4., 4.)).with_group("sphere"));
     world.add(Arc::new(object::Sphere::new(
         Point3::new(0., 7., 0.),
         2.,
@@ -70,7 +120,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     bvh_root.add(Arc::new(BVHNode::new(world, &mut rng)));
 
     // Create scene
-    let scene = Scene::new(
+    Scene::new(
         bvh_root,
         camera,
         max_depth,
@@ -78,14 +128,5 @@ fn main() -> Result<(), Box<dyn Error>> {
         IMAGE_WIDTH,
         IMAGE_HEIGHT,
         Colour::new(0., 0., 0.),
-    );
-
-    // Render the scene to a frame buffer
-    scene.render(&mut pixels)?;
-
-    // Write the frame buffer to a file
-    image::png::write_image::<&Path, IMAGE_WIDTH, IMAGE_HEIGHT>(&pixels, Path::new("image.png"))?;
-    eprintln!("Saved image");
-
-    Ok(())
+    )
 }
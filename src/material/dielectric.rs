@@ -4,18 +4,31 @@ use rand::{rngs, Rng};
 
 use crate::{object::HitRecord, ray::Ray, texture::SolidColour, texture::Texture, Colour};
 
-use super::{Behaviour, Material, MaterialScatterResult};
+use super::{Behaviour, Material, MaterialScatterResult, Medium, MediumStack};
 
 #[derive(Debug)]
 pub struct Dielectric {
     attenuation: Arc<dyn Texture>,
     ir: f64, // Index of refraction
+    priority: i32,
 }
 
 impl Dielectric {
     pub fn new(ir: f64) -> Self {
         let attenuation = Arc::new(SolidColour::new(Colour::new(1., 1., 1.)));
-        Self { ir, attenuation }
+        Self {
+            ir,
+            attenuation,
+            priority: 0,
+        }
+    }
+
+    /// Sets the priority of the volume enclosed by this material. Where
+    /// volumes overlap, such as water modelled slightly inside the walls of a
+    /// glass, the surfaces of lower priority volumes are ignored.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
     }
 
     fn reflectance(&self, cosine: f64, ref_idx: f64) -> f64 {
@@ -31,10 +44,23 @@ impl Material for Dielectric {
         hitrec: &HitRecord,
         rng: &mut rngs::SmallRng,
     ) -> MaterialScatterResult {
+        self.scatter_in_media(r, hitrec, &MediumStack::new(), rng)
+    }
+
+    fn scatter_in_media(
+        &self,
+        r: &Ray,
+        hitrec: &HitRecord,
+        media: &MediumStack,
+        rng: &mut rngs::SmallRng,
+    ) -> MaterialScatterResult {
+        // The index of refraction on the other side of the surface depends on
+        // which other media the ray is inside
+        let outside_ir = media.outside_ir(MediumStack::medium_id(hitrec.mat));
         let refraction_ratio = if hitrec.front_face {
-            1.0 / self.ir
+            outside_ir / self.ir
         } else {
-            self.ir
+            self.ir / outside_ir
         };
 
         let unit_direction = r.direction.unit();
@@ -51,9 +77,12 @@ impl Material for Dielectric {
         let scattered = Ray::new(hitrec.point, direction, r.time);
         MaterialScatterResult::new(
             Behaviour::Scatter,
-            self.attenuation
-                .get_value(hitrec.u, hitrec.v, &hitrec.point),
+            self.attenuation.value(hitrec),
             scattered,
         )
     }
+
+    fn medium(&self) -> Option<Medium<'_>> {
+        Some(Medium::new(self.ir, self.priority))
+    }
 }
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use rand::rngs;
 
 use crate::{
+    light_group,
     object::HitRecord,
     ray::Ray,
     texture::{SolidColour, Texture},
@@ -15,17 +16,26 @@ use super::{Behaviour, Material, MaterialScatterResult};
 #[derive(Debug)]
 pub struct DiffuseLight {
     emit: Arc<dyn Texture>,
+    group: String,
 }
 
 impl DiffuseLight {
     pub fn new(emit: Arc<dyn Texture>) -> Self {
-        Self { emit }
+        Self {
+            emit,
+            group: light_group::DEFAULT_LIGHT_GROUP.to_string(),
+        }
     }
 
     pub fn from_colour(emit: Colour) -> Self {
-        Self {
-            emit: Arc::new(SolidColour::new(emit)),
-        }
+        Self::new(Arc::new(SolidColour::new(emit)))
+    }
+
+    /// Tags the light with a light group, so that its contribution is
+    /// rendered into a separate layer by `Scene::render_light_groups`.
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.group = group.to_string();
+        self
     }
 }
 
@@ -46,4 +56,8 @@ impl Material for DiffuseLight {
     fn emitted(&self, u: f64, v: f64, p: &Point3) -> Colour {
         self.emit.get_value(u, v, p)
     }
+
+    fn light_group(&self) -> &str {
+        &self.group
+    }
 }
@@ -38,7 +38,7 @@ impl Material for Isotropic {
     ) -> MaterialScatterResult {
         MaterialScatterResult::new(
             Behaviour::Scatter,
-            self.albedo.get_value(hitrec.u, hitrec.v, &hitrec.point),
+            self.albedo.value(hitrec),
             Ray::new(
                 hitrec.point,
                 Vec3::random_in_unit_sphere(rng).unit(),
@@ -44,7 +44,7 @@ impl Material for Lambertian {
 
         MaterialScatterResult::new(
             Behaviour::Scatter,
-            self.albedo.get_value(hitrec.u, hitrec.v, &hitrec.point),
+            self.albedo.value(hitrec),
             Ray::new(hitrec.point, scatter_direction, r.time),
         )
     }
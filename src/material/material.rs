@@ -2,7 +2,9 @@ use std::fmt;
 
 use rand::rngs;
 
-use crate::{object::HitRecord, ray::Ray, Colour, Point3};
+use crate::{light_group, object::HitRecord, ray::Ray, Colour, Point3};
+
+use super::{Medium, MediumStack};
 
 #[derive(Debug)]
 pub enum Behaviour {
@@ -35,7 +37,30 @@ pub trait Material: fmt::Debug + Send + Sync {
         rng: &mut rngs::SmallRng,
     ) -> MaterialScatterResult;
 
+    /// Scatters a ray that is travelling through the given media. By default
+    /// the media are ignored.
+    fn scatter_in_media(
+        &self,
+        r: &Ray,
+        hitrec: &HitRecord,
+        _media: &MediumStack,
+        rng: &mut rngs::SmallRng,
+    ) -> MaterialScatterResult {
+        self.scatter(r, hitrec, rng)
+    }
+
     fn emitted(&self, _u: f64, _v: f64, _p: &Point3) -> Colour {
         Colour::new(0., 0., 0.)
     }
+
+    /// The medium enclosed by surfaces with this material, if rays can pass
+    /// through the surface into a volume with its own index of refraction.
+    fn medium(&self) -> Option<Medium<'_>> {
+        None
+    }
+
+    /// The name of the light group that any emitted light is attributed to.
+    fn light_group(&self) -> &str {
+        light_group::DEFAULT_LIGHT_GROUP
+    }
 }
@@ -0,0 +1,229 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::Colour;
+
+use super::{Isotropic, Material};
+
+/// The maximum number of nested media that a ray can be inside at once.
+/// Entering further media than this is ignored.
+const MAX_NESTED_MEDIA: usize = 8;
+
+/// Samples the distance that light travels through a homogeneous medium with
+/// the given density before it collides with a particle.
+pub fn free_flight_distance(density: f64, rng: &mut impl Rng) -> f64 {
+    -(1. - rng.gen::<f64>()).ln() / density
+}
+
+/// The optical properties of the volume enclosed by a surface.
+#[derive(Debug, Clone, Copy)]
+pub struct Medium<'a> {
+    /// Index of refraction of the volume.
+    pub ir: f64,
+    /// Where volumes overlap, the volume with the highest priority is the one
+    /// the ray is considered to be inside. Surfaces of lower priority volumes
+    /// inside it are ignored.
+    pub priority: i32,
+    /// How light scatters inside the volume, if it is not perfectly clear.
+    pub scattering: Option<&'a VolumeScattering>,
+}
+
+impl<'a> Medium<'a> {
+    pub fn new(ir: f64, priority: i32) -> Self {
+        Self {
+            ir,
+            priority,
+            scattering: None,
+        }
+    }
+
+    pub fn with_scattering(mut self, scattering: &'a VolumeScattering) -> Self {
+        self.scattering = Some(scattering);
+        self
+    }
+}
+
+/// Scattering inside a volume, as used for subsurface scattering.
+#[derive(Debug)]
+pub struct VolumeScattering {
+    /// The extinction coefficient of each colour channel, which is the
+    /// density of particles that light collides with in the volume.
+    pub extinction: Colour,
+    /// The material that scatters light at each collision.
+    pub phase_function: Arc<dyn Material>,
+}
+
+impl VolumeScattering {
+    /// Creates isotropic scattering, where `albedo` is the fraction of light
+    /// that survives each collision.
+    pub fn new(albedo: Colour, extinction: Colour) -> Self {
+        Self {
+            extinction,
+            phase_function: Arc::new(Isotropic::from_colour(albedo)),
+        }
+    }
+
+    /// The highest extinction across the colour channels, used to sample
+    /// tentative collisions in the volume.
+    pub fn majorant(&self) -> f64 {
+        self.extinction
+            .x
+            .max(self.extinction.y)
+            .max(self.extinction.z)
+    }
+
+    /// Decides whether a tentative collision is a real collision with the
+    /// volume, rather than a null collision that the ray passes straight
+    /// through. The choice is made in proportion to the densities of each,
+    /// weighted by the throughput of the path, and the throughput weight of
+    /// the choice is returned with it.
+    pub fn sample_collision(&self, throughput: Colour, rng: &mut impl Rng) -> (bool, Colour) {
+        let majorant = self.majorant();
+        let null = Colour::new(majorant, majorant, majorant) - self.extinction;
+        let real_density = (self.extinction * throughput).dot(Colour::new(1., 1., 1.));
+        let null_density = (null * throughput).dot(Colour::new(1., 1., 1.));
+        if real_density + null_density <= 0. {
+            return (true, Colour::zeros());
+        }
+
+        let p_real = real_density / (real_density + null_density);
+        if rng.gen::<f64>() < p_real {
+            (true, self.extinction / (majorant * p_real))
+        } else {
+            (false, null / (majorant * (1. - p_real)))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MediumEntry<'a> {
+    id: usize,
+    medium: Medium<'a>,
+}
+
+/// The stack of media that a ray is currently travelling through, used to
+/// find the index of refraction on either side of a surface between nested
+/// volumes (such as water inside a glass).
+#[derive(Debug, Clone, Copy)]
+pub struct MediumStack<'a> {
+    entries: [MediumEntry<'a>; MAX_NESTED_MEDIA],
+    len: usize,
+}
+
+impl<'a> MediumStack<'a> {
+    /// A stack for a ray that is not inside any medium.
+    pub fn new() -> Self {
+        Self {
+            entries: [MediumEntry {
+                id: 0,
+                medium: Medium::new(1., 0),
+            }; MAX_NESTED_MEDIA],
+            len: 0,
+        }
+    }
+
+    /// Identifies the medium of a material by the address of the material,
+    /// so that every object sharing a material shares a medium.
+    pub fn medium_id(mat: &Arc<dyn Material>) -> usize {
+        Arc::as_ptr(mat) as *const () as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &MediumEntry<'a>> {
+        self.entries[..self.len].iter()
+    }
+
+    /// The medium that the ray is currently travelling through, which is the
+    /// one with the highest priority.
+    pub fn current(&self) -> Option<&Medium<'a>> {
+        self.top(None).map(|entry| &entry.medium)
+    }
+
+    /// The medium with the highest priority, ignoring the medium with the
+    /// given id. Later entries win ties.
+    fn top_excluding(&self, id: usize) -> Option<&MediumEntry<'a>> {
+        self.top(Some(id))
+    }
+
+    fn top(&self, exclude: Option<usize>) -> Option<&MediumEntry<'a>> {
+        self.iter().filter(|entry| Some(entry.id) != exclude).fold(
+            None,
+            |top: Option<&MediumEntry<'a>>, entry| match top {
+                Some(top) if top.medium.priority > entry.medium.priority => Some(top),
+                _ => Some(entry),
+            },
+        )
+    }
+
+    /// The index of refraction on the other side of the surface of the medium
+    /// with the given id, which is 1 if the ray is in no other medium.
+    pub fn outside_ir(&self, id: usize) -> f64 {
+        self.top_excluding(id).map_or(1., |entry| entry.medium.ir)
+    }
+
+    /// Whether a hit on the surface of a medium is a real interface, or should
+    /// be ignored because the ray is inside a medium with a higher priority.
+    pub fn is_true_intersection(&self, id: usize, medium: &Medium) -> bool {
+        self.top_excluding(id)
+            .is_none_or(|entry| medium.priority >= entry.medium.priority)
+    }
+
+    /// Gets the stack after the ray passes through the surface of a medium,
+    /// entering it if `entering` is true and leaving it otherwise.
+    pub fn crossed(&self, id: usize, medium: Medium<'a>, entering: bool) -> Self {
+        let mut stack = *self;
+        if entering {
+            if stack.len < MAX_NESTED_MEDIA {
+                stack.entries[stack.len] = MediumEntry { id, medium };
+                stack.len += 1;
+            }
+        } else if let Some(i) = stack.entries[..stack.len]
+            .iter()
+            .rposition(|entry| entry.id == id)
+        {
+            stack.entries.copy_within(i + 1..stack.len, i);
+            stack.len -= 1;
+        }
+        stack
+    }
+}
+
+impl Default for MediumStack<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Medium, MediumStack};
+
+    #[test]
+    fn glass_of_water() {
+        let glass = Medium::new(1.5, 2);
+        let water = Medium::new(1.33, 1);
+        let (glass_id, water_id) = (1, 2);
+
+        let air = MediumStack::new();
+        assert_eq!(air.outside_ir(glass_id), 1.);
+
+        // Enter the glass wall, then the water surface inside it is ignored
+        let in_glass = air.crossed(glass_id, glass, true);
+        assert!(!in_glass.is_true_intersection(water_id, &water));
+        let in_both = in_glass.crossed(water_id, water, true);
+
+        // Leaving the glass wall goes into the water
+        assert!(in_both.is_true_intersection(glass_id, &glass));
+        assert_eq!(in_both.outside_ir(glass_id), 1.33);
+        let in_water = in_both.crossed(glass_id, glass, false);
+        assert_eq!(in_water.outside_ir(glass_id), 1.33);
+        assert_eq!(in_water.outside_ir(water_id), 1.);
+
+        let out = in_water.crossed(water_id, water, false);
+        assert!(out.is_empty());
+    }
+}
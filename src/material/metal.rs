@@ -49,10 +49,6 @@ impl Material for Metal {
             _ => Behaviour::Absorb, // This is likely needed for NaNs
         };
 
-        MaterialScatterResult::new(
-            behaviour,
-            self.albedo.get_value(hitrec.u, hitrec.v, &hitrec.point),
-            scattered,
-        )
+        MaterialScatterResult::new(behaviour, self.albedo.value(hitrec), scattered)
     }
 }
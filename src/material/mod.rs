@@ -3,11 +3,15 @@ pub mod diffuse_light;
 pub mod isotropic;
 pub mod lambertian;
 pub mod material;
+pub mod medium;
 pub mod metal;
+pub mod subsurface;
 
 pub use dielectric::Dielectric;
 pub use diffuse_light::DiffuseLight;
 pub use isotropic::Isotropic;
 pub use lambertian::Lambertian;
 pub use material::{Behaviour, Material, MaterialScatterResult};
+pub use medium::{free_flight_distance, Medium, MediumStack, VolumeScattering};
 pub use metal::Metal;
+pub use subsurface::Subsurface;
@@ -0,0 +1,158 @@
+use rand::rngs;
+
+use crate::{object::HitRecord, ray::Ray, Colour};
+
+use super::{Dielectric, Material, MaterialScatterResult, Medium, MediumStack, VolumeScattering};
+
+/// A translucent material such as skin, wax, marble or milk, where light
+/// enters the surface and scatters many times inside the object before
+/// leaving again.
+///
+/// The surface is a smooth dielectric interface, and the scene performs a
+/// random walk through the enclosed volume while rays are inside it, so this
+/// material must be used on closed geometry.
+#[derive(Debug)]
+pub struct Subsurface {
+    surface: Dielectric,
+    ir: f64,
+    priority: i32,
+    scattering: VolumeScattering,
+}
+
+impl Subsurface {
+    /// Creates a new subsurface material, where `albedo` is the fraction of
+    /// light that survives each scattering event inside the volume, and
+    /// `mean_free_path` is the average distance between those events.
+    pub fn new(albedo: Colour, mean_free_path: f64, ir: f64) -> Self {
+        let extinction = 1. / mean_free_path;
+        Self::with_scattering(
+            VolumeScattering::new(albedo, Colour::new(extinction, extinction, extinction)),
+            ir,
+        )
+    }
+
+    /// Creates a new subsurface material from per channel scattering and
+    /// absorption coefficients.
+    pub fn from_coefficients(scattering: Colour, absorption: Colour, ir: f64) -> Self {
+        let extinction = scattering + absorption;
+        Self::with_scattering(
+            VolumeScattering::new(scattering / extinction, extinction),
+            ir,
+        )
+    }
+
+    fn with_scattering(scattering: VolumeScattering, ir: f64) -> Self {
+        Self {
+            surface: Dielectric::new(ir),
+            ir,
+            priority: 0,
+            scattering,
+        }
+    }
+
+    /// Sets the priority of the volume, see `Dielectric::with_priority`.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.surface = self.surface.with_priority(priority);
+        self.priority = priority;
+        self
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(
+        &self,
+        r: &Ray,
+        hitrec: &HitRecord,
+        rng: &mut rngs::SmallRng,
+    ) -> MaterialScatterResult {
+        self.surface.scatter(r, hitrec, rng)
+    }
+
+    fn scatter_in_media(
+        &self,
+        r: &Ray,
+        hitrec: &HitRecord,
+        media: &MediumStack,
+        rng: &mut rngs::SmallRng,
+    ) -> MaterialScatterResult {
+        self.surface.scatter_in_media(r, hitrec, media, rng)
+    }
+
+    fn medium(&self) -> Option<Medium<'_>> {
+        Some(Medium::new(self.ir, self.priority).with_scattering(&self.scattering))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{
+        camera::CameraBuilder,
+        object::{HittableList, Sphere},
+        ray::Ray,
+        scene::Scene,
+        vec3::Vec3,
+        Colour, Point3,
+    };
+
+    use super::Subsurface;
+
+    fn furnace(mat: Subsurface) -> Colour {
+        // An object lit evenly from all sides by a white background
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0., 0., 0.),
+            1.,
+            Arc::new(mat),
+        )));
+        let camera = CameraBuilder::new()
+            .origin(Point3::new(0., 0., -4.))
+            .look_at(Point3::new(0., 0., 0.))
+            .build();
+        let scene = Scene::new(world, camera, 400, 1, 1, 1, Colour::new(1., 1., 1.));
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0., 0., -4.), Vec3::new(0., 0., 1.), 0.);
+        let samples = 1000;
+        (0..samples).fold(Colour::zeros(), |total, _| {
+            total + scene.ray_colour(&r, 400, &mut rng)
+        }) / samples as f64
+    }
+
+    #[test]
+    fn white_furnace_conserves_energy() {
+        // A non-absorbing object should be invisible against the background
+        let colour = furnace(Subsurface::new(Colour::new(1., 1., 1.), 0.5, 1.3));
+        assert!(
+            (colour - Colour::new(1., 1., 1.)).length() < 0.05,
+            "{:?}",
+            colour
+        );
+    }
+
+    #[test]
+    fn white_furnace_with_coloured_extinction() {
+        // Channels that collide at different rates all stay unabsorbed
+        let colour = furnace(Subsurface::from_coefficients(
+            Colour::new(1., 2., 4.),
+            Colour::zeros(),
+            1.3,
+        ));
+        assert!(
+            (colour - Colour::new(1., 1., 1.)).length() < 0.05,
+            "{:?}",
+            colour
+        );
+    }
+
+    #[test]
+    fn coefficients_give_extinction() {
+        let mat =
+            Subsurface::from_coefficients(Colour::new(3., 2., 1.), Colour::new(0., 2., 4.), 1.);
+
+        assert_eq!(mat.scattering.extinction, Colour::new(3., 4., 5.));
+    }
+}
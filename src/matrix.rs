@@ -0,0 +1,217 @@
+use std::ops::Mul;
+
+use crate::{vec3::Vec3, Point3};
+
+/// A 4x4 matrix of an affine transform, acting on column vectors.
+///
+/// Transforms are combined by multiplying, with the rightmost applied first,
+/// so `translation * rotation * scaling` scales, then rotates, then
+/// translates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4 {
+    pub rows: [[f64; 4]; 4],
+}
+
+impl Matrix4 {
+    pub const IDENTITY: Self = Self::new([
+        [1., 0., 0., 0.],
+        [0., 1., 0., 0.],
+        [0., 0., 1., 0.],
+        [0., 0., 0., 1.],
+    ]);
+
+    pub const fn new(rows: [[f64; 4]; 4]) -> Self {
+        Self { rows }
+    }
+
+    /// Creates a matrix from its columns, as stored by column-major formats
+    /// such as glTF.
+    pub fn from_columns(columns: [[f64; 4]; 4]) -> Self {
+        Self::new(columns).transpose()
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        Self::new([
+            [1., 0., 0., offset.x],
+            [0., 1., 0., offset.y],
+            [0., 0., 1., offset.z],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn scaling(scale: Vec3) -> Self {
+        Self::new([
+            [scale.x, 0., 0., 0.],
+            [0., scale.y, 0., 0.],
+            [0., 0., scale.z, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Creates a rotation by an angle in degrees about an axis through the
+    /// origin, anticlockwise when looking back along the axis.
+    pub fn rotation(axis: Vec3, angle: f64) -> Self {
+        let Vec3 { x, y, z } = axis.unit();
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let c = 1. - cos;
+        Self::new([
+            [
+                cos + x * x * c,
+                x * y * c - z * sin,
+                x * z * c + y * sin,
+                0.,
+            ],
+            [
+                y * x * c + z * sin,
+                cos + y * y * c,
+                y * z * c - x * sin,
+                0.,
+            ],
+            [
+                z * x * c - y * sin,
+                z * y * c + x * sin,
+                cos + z * z * c,
+                0.,
+            ],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Creates a shear, where each factor adds a multiple of the second
+    /// named axis to the first, so `xy` moves x in proportion to y.
+    pub fn shear(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Self {
+        Self::new([
+            [1., xy, xz, 0.],
+            [yx, 1., yz, 0.],
+            [zx, zy, 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut rows = [[0.; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.rows[j][i];
+            }
+        }
+        Self::new(rows)
+    }
+
+    /// Inverts the matrix by Gauss-Jordan elimination, returning `None` if
+    /// it is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.rows;
+        let mut inv = Self::IDENTITY.rows;
+
+        for col in 0..4 {
+            // Pivot on the largest remaining value in the column
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1. / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                let factor = a[row][col];
+                if row == col || factor == 0. {
+                    continue;
+                }
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+        Some(Self::new(inv))
+    }
+
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        self.transform_vector(p) + Vec3::new(self.rows[0][3], self.rows[1][3], self.rows[2][3])
+    }
+
+    /// Transforms a direction, ignoring the translation.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let [r0, r1, r2, _] = self.rows;
+        Vec3::new(
+            r0[0] * v.x + r0[1] * v.y + r0[2] * v.z,
+            r1[0] * v.x + r1[1] * v.y + r1[2] * v.z,
+            r2[0] * v.x + r2[1] * v.y + r2[2] * v.z,
+        )
+    }
+
+    /// Transforms a surface normal, given the inverse of this matrix. The
+    /// result isn't normalised.
+    pub fn transform_normal(inverse: &Self, n: Vec3) -> Vec3 {
+        inverse.transpose().transform_vector(n)
+    }
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let mut rows = [[0.; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.rows[i][k] * rhs.rows[k][j]).sum();
+            }
+        }
+        Self::new(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{vec3::Vec3, Point3};
+
+    use super::Matrix4;
+
+    #[test]
+    fn compose_and_invert() {
+        let m = Matrix4::translation(Vec3::new(1., 2., 3.))
+            * Matrix4::rotation(Vec3::new(0., 1., 0.), 90.)
+            * Matrix4::scaling(Vec3::new(2., 1., 1.));
+        let p = m.transform_point(Point3::new(1., 0., 0.));
+        assert!(p.is_close(&Point3::new(1., 2., 1.)));
+
+        let inverse = m.inverse().unwrap();
+        assert!(inverse
+            .transform_point(p)
+            .is_close(&Point3::new(1., 0., 0.)));
+        let identity = m * inverse;
+        for (row, expected) in identity.rows.iter().zip(Matrix4::IDENTITY.rows) {
+            for (value, expected) in row.iter().zip(expected) {
+                assert!((value - expected).abs() < 1e-12);
+            }
+        }
+
+        assert!(Matrix4::scaling(Vec3::new(1., 0., 1.)).inverse().is_none());
+    }
+
+    #[test]
+    fn normals_stay_perpendicular() {
+        let m = Matrix4::shear(0.5, 0., 0., 0., 0.3, 0.) * Matrix4::scaling(Vec3::new(3., 1., 2.));
+        let inverse = m.inverse().unwrap();
+
+        // A plane spanned by two tangents, and its normal
+        let (t1, t2) = (Vec3::new(1., 1., 0.), Vec3::new(0., 1., 1.));
+        let n = Matrix4::transform_normal(&inverse, t1.cross(t2));
+        assert!(n.dot(m.transform_vector(t1)).abs() < 1e-12);
+        assert!(n.dot(m.transform_vector(t2)).abs() < 1e-12);
+    }
+}
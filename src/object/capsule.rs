@@ -0,0 +1,154 @@
+use std::{f64::consts, sync::Arc};
+
+use rand::rngs;
+
+use crate::{aabb::AABB, interval::Interval, material, ray::Ray, stats, vec3::Vec3, Point3};
+
+use super::{
+    frame::{self, Frame},
+    HitRecord, Hittable,
+};
+
+/// A cylinder with hemispherical ends, made of every point within a radius
+/// of the segment between two points.
+///
+/// The hit record's u is the angle around the axis and v the distance along
+/// the outline from the bottom pole to the top pole.
+#[derive(Debug)]
+pub struct Capsule {
+    frame: Frame,
+    length: f64,
+    radius: f64,
+    mat: Arc<dyn material::Material>,
+    aabb: AABB,
+}
+
+impl Capsule {
+    pub fn new(a: Point3, b: Point3, radius: f64, mat: Arc<dyn material::Material>) -> Self {
+        let frame = Frame::new(a, b - a);
+        let length = (b - a).length();
+        let rvec = Vec3::new(radius, radius, radius);
+        let aabb = AABB::from_boxes(
+            &AABB::from_points(a - rvec, a + rvec),
+            &AABB::from_points(b - rvec, b + rvec),
+        );
+        Self {
+            frame,
+            length,
+            radius,
+            mat,
+            aabb,
+        }
+    }
+
+    /// The distance along the outline from the bottom pole, as a fraction
+    /// of the whole outline.
+    fn outline_fraction(&self, p: Vec3) -> f64 {
+        let quarter = consts::FRAC_PI_2 * self.radius;
+        let s = if p.y < 0. {
+            self.radius * (-p.y / self.radius).clamp(-1., 1.).acos()
+        } else if p.y > self.length {
+            let above = (p.y - self.length) / self.radius;
+            quarter + self.length + quarter - self.radius * above.clamp(-1., 1.).acos()
+        } else {
+            quarter + p.y
+        };
+        s / (2. * quarter + self.length)
+    }
+}
+
+impl Hittable for Capsule {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("Capsule");
+
+        let (o, d) = self.frame.local_ray(r);
+        let r2 = self.radius * self.radius;
+
+        // The side, between the centres of the ends
+        let a = d.x * d.x + d.z * d.z;
+        let half_b = o.x * d.x + o.z * d.z;
+        let c = o.x * o.x + o.z * o.z - r2;
+        let side = |t: f64| {
+            let p = o + d * t;
+            (0. ..=self.length)
+                .contains(&p.y)
+                .then(|| (t, (p, Vec3::new(p.x, 0., p.z))))
+        };
+        let sides = frame::quadratic_roots(a, half_b, c)
+            .map_or([None, None], |(t0, t1)| [side(t0), side(t1)]);
+
+        // The hemispheres, beyond each end of the segment
+        let hemispheres = [0., self.length].map(|centre| {
+            let oc = o - Vec3::new(0., centre, 0.);
+            let end = |t: f64| {
+                let p = o + d * t;
+                let beyond = if centre == 0. {
+                    p.y < 0.
+                } else {
+                    p.y > self.length
+                };
+                beyond.then(|| (t, (p, oc + d * t)))
+            };
+            frame::quadratic_roots(1., oc.dot(d), oc.dot(oc) - r2)
+                .map_or([None, None], |(t0, t1)| [end(t0), end(t1)])
+        });
+
+        let candidates = [
+            sides[0],
+            sides[1],
+            hemispheres[0][0],
+            hemispheres[0][1],
+            hemispheres[1][0],
+            hemispheres[1][1],
+        ];
+
+        let (t, (p, normal)) = frame::closest(candidates.into_iter().flatten(), ray_t)?;
+        let u = (p.z.atan2(p.x) + consts::PI) / consts::TAU;
+        let v = self.outline_fraction(p);
+        Some(self.frame.hit_record(r, t, normal, (u, v), &self.mat))
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{interval, material, object::Hittable, ray::Ray, vec3::Vec3, Colour, Point3};
+
+    use super::Capsule;
+
+    #[test]
+    fn hit_side_and_ends() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let capsule = Capsule::new(Point3::new(0., -1., 0.), Point3::new(0., 1., 0.), 0.5, mat);
+        let bbox = capsule.bounding_box();
+        assert!((bbox.y.max - 1.5).abs() < 1e-9 && (bbox.x.min + 0.5).abs() < 1e-9);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0., 0., 5.), Vec3::new(0., 0., -1.), 0.);
+        let hit = capsule.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 4.5).abs() < 1e-9);
+        assert!(hit.normal.is_close(&Vec3::new(0., 0., 1.)));
+        assert!((hit.v - 0.5).abs() < 1e-9);
+
+        let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        let hit = capsule.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 3.5).abs() < 1e-9);
+        assert!(hit.normal.is_close(&Vec3::new(0., 1., 0.)));
+        assert!((hit.v - 1.).abs() < 1e-9);
+
+        // On the rounded end, off the axis
+        let r = Ray::new(Point3::new(0.3, -5., 0.), Vec3::new(0., 1., 0.), 0.);
+        let hit = capsule.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!(hit.point.is_close(&Point3::new(0.3, -1.4, 0.)));
+        assert!(hit.normal.is_close(&Vec3::new(0.6, -0.8, 0.)));
+    }
+}
@@ -0,0 +1,155 @@
+use std::{f64::consts, sync::Arc};
+
+use rand::rngs;
+
+use crate::{aabb::AABB, interval::Interval, material, ray::Ray, stats, vec3::Vec3, Point3};
+
+use super::{
+    frame::{self, Frame},
+    HitRecord, Hittable,
+};
+
+/// A cone, or a frustum if it's cut off before the tip, with its ends closed
+/// by flat caps.
+///
+/// On the side, the hit record's u is the angle around the axis and v the
+/// distance along it from the base. The caps are mapped flat onto the unit
+/// square.
+#[derive(Debug)]
+pub struct Cone {
+    frame: Frame,
+    height: f64,
+    base_radius: f64,
+    top_radius: f64,
+    mat: Arc<dyn material::Material>,
+    aabb: AABB,
+}
+
+impl Cone {
+    /// Creates a cone with a circular base, narrowing to a point at `apex`.
+    pub fn new(
+        base: Point3,
+        base_radius: f64,
+        apex: Point3,
+        mat: Arc<dyn material::Material>,
+    ) -> Self {
+        Self::frustum(base, base_radius, apex, 0., mat)
+    }
+
+    /// Creates a cone cut off at `top`, where it has a smaller radius.
+    pub fn frustum(
+        base: Point3,
+        base_radius: f64,
+        top: Point3,
+        top_radius: f64,
+        mat: Arc<dyn material::Material>,
+    ) -> Self {
+        assert!(
+            base_radius > 0. && top_radius >= 0.,
+            "cone radii must not be negative"
+        );
+        let frame = Frame::new(base, top - base);
+        let height = (top - base).length();
+        let aabb = AABB::from_boxes(
+            &frame.disk_box(0., base_radius),
+            &frame.disk_box(height, top_radius),
+        )
+        .pad();
+        Self {
+            frame,
+            height,
+            base_radius,
+            top_radius,
+            mat,
+            aabb,
+        }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("Cone");
+
+        let (o, d) = self.frame.local_ray(r);
+
+        // The radius changes linearly with height, so the side satisfies
+        // x^2 + z^2 = (r0 + k y)^2
+        let k = (self.top_radius - self.base_radius) / self.height;
+        let radius_at_origin = self.base_radius + k * o.y;
+        let a = d.x * d.x + d.z * d.z - k * k * d.y * d.y;
+        let half_b = o.x * d.x + o.z * d.z - k * radius_at_origin * d.y;
+        let c = o.x * o.x + o.z * o.z - radius_at_origin * radius_at_origin;
+        let side = |t: f64| {
+            let p = o + d * t;
+            (0. ..=self.height).contains(&p.y).then(|| {
+                let radius = self.base_radius + k * p.y;
+                let u = (p.z.atan2(p.x) + consts::PI) / consts::TAU;
+                let normal = Vec3::new(p.x, -radius * k, p.z);
+                (t, (normal, (u, p.y / self.height)))
+            })
+        };
+        let sides = frame::quadratic_roots(a, half_b, c)
+            .map_or([None, None], |(t0, t1)| [side(t0), side(t1)]);
+
+        let cap = |y: f64, radius: f64, normal: f64| {
+            if d.y.abs() <= 1e-12 {
+                return None;
+            }
+            let t = (y - o.y) / d.y;
+            let p = o + d * t;
+            (radius > 0. && p.x * p.x + p.z * p.z <= radius * radius).then(|| {
+                let uv = (0.5 + 0.5 * p.x / radius, 0.5 + 0.5 * p.z / radius);
+                (t, (Vec3::new(0., normal, 0.), uv))
+            })
+        };
+        let candidates = [
+            sides[0],
+            sides[1],
+            cap(0., self.base_radius, -1.),
+            cap(self.height, self.top_radius, 1.),
+        ];
+
+        let (t, (normal, uv)) = frame::closest(candidates.into_iter().flatten(), ray_t)?;
+        Some(self.frame.hit_record(r, t, normal, uv, &self.mat))
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{interval, material, object::Hittable, ray::Ray, vec3::Vec3, Colour, Point3};
+
+    use super::Cone;
+
+    #[test]
+    fn hit_side_and_base() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        // A 45 degree cone standing on the origin
+        let cone = Cone::new(Point3::zeros(), 1., Point3::new(0., 1., 0.), mat);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(5., 0.5, 0.), Vec3::new(-1., 0., 0.), 0.);
+        let hit = cone.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 4.5).abs() < 1e-9);
+        assert!(hit.normal.is_close(&Vec3::new(1., 1., 0.).unit()));
+        assert!((hit.v - 0.5).abs() < 1e-9);
+
+        let r = Ray::new(Point3::new(0.2, -5., 0.), Vec3::new(0., 1., 0.), 0.);
+        let hit = cone.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 5.).abs() < 1e-9);
+        assert!(hit.normal.is_close(&Vec3::new(0., -1., 0.)));
+
+        // Above the tip, where the mirrored half of the double cone would be
+        let r = Ray::new(Point3::new(5., 1.5, 0.), Vec3::new(-1., 0., 0.), 0.);
+        assert!(cone.hit(&r, &interval::UNIVERSE, &mut rng).is_none());
+    }
+}
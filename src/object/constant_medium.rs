@@ -1,16 +1,23 @@
 use std::sync::Arc;
 
-use rand::{rngs, Rng};
+use rand::rngs;
 
-use crate::{interval, material, texture, vec3::Vec3, Colour};
+use crate::{interval, material, stats, texture, vec3::Vec3, Colour};
 
 use super::{HitRecord, Hittable};
 
+/// A participating medium of uniform density, such as smoke or fog, filling
+/// the inside of a boundary.
+///
+/// Collisions are sampled from the boundary alone, without consulting the
+/// stack of media the ray is inside, so priorities don't apply to these
+/// volumes. Where two of them overlap their densities add up. They can still
+/// be placed inside dielectrics, such as fog in a glass box.
 #[derive(Debug)]
 pub struct ConstantMedium {
     phase_function: Arc<dyn material::Material>,
     boundary: Arc<dyn Hittable>,
-    neg_inv_density: f64,
+    density: f64,
 }
 
 impl ConstantMedium {
@@ -22,7 +29,7 @@ impl ConstantMedium {
         Self {
             phase_function: Arc::new(material::Isotropic::new(texture)),
             boundary,
-            neg_inv_density: -1. / density,
+            density,
         }
     }
 
@@ -30,7 +37,7 @@ impl ConstantMedium {
         Self {
             phase_function: Arc::new(material::Isotropic::from_colour(colour)),
             boundary,
-            neg_inv_density: -1. / density,
+            density,
         }
     }
 }
@@ -42,6 +49,8 @@ impl Hittable for ConstantMedium {
         ray_t: &crate::interval::Interval,
         rng: &mut rngs::SmallRng,
     ) -> Option<super::HitRecord> {
+        stats::record_hit("ConstantMedium");
+
         let mut hitrec1 = self.boundary.hit(r, &interval::UNIVERSE, rng)?;
         let mut hitrec2 = self.boundary.hit(
             r,
@@ -67,7 +76,7 @@ impl Hittable for ConstantMedium {
         // TODO: Check this since this is probably already a unit vector
         let ray_length = r.direction.length();
         let distance_inside_boundary = (hitrec2.t - hitrec1.t) * ray_length;
-        let hit_distance = self.neg_inv_density * rng.gen::<f64>().ln();
+        let hit_distance = material::free_flight_distance(self.density, rng);
 
         if hit_distance > distance_inside_boundary {
             return None;
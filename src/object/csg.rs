@@ -0,0 +1,281 @@
+use std::sync::Arc;
+
+use rand::rngs;
+
+use crate::{
+    aabb::AABB,
+    interval::{self, Interval},
+    ray::Ray,
+    stats,
+};
+
+use super::{HitRecord, Hittable};
+
+/// How two solids are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+    /// Everything inside either solid.
+    Union,
+    /// Everything inside both solids.
+    Intersection,
+    /// Everything inside the first solid but not the second.
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// A solid made by combining two closed objects with constructive solid
+/// geometry. Each part of the surface keeps the material of the object it
+/// came from.
+///
+/// CSG objects can themselves be combined, so complex solids are built up
+/// from trees of operations.
+#[derive(Debug)]
+pub struct Csg {
+    operation: CsgOperation,
+    left: Arc<dyn Hittable>,
+    right: Arc<dyn Hittable>,
+    bbox: AABB,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        let (a, b) = (left.bounding_box(), right.bounding_box());
+        let bbox = match operation {
+            CsgOperation::Union => AABB::from_boxes(a, b),
+            CsgOperation::Intersection => {
+                let overlap = |a: &Interval, b: &Interval| {
+                    let (min, max) = (a.min.max(b.min), a.max.min(b.max));
+                    // Nothing can be hit if the boxes don't overlap
+                    if min > max {
+                        interval::EMPTY
+                    } else {
+                        Interval::new(min, max)
+                    }
+                };
+                AABB::new(
+                    overlap(&a.x, &b.x),
+                    overlap(&a.y, &b.y),
+                    overlap(&a.z, &b.z),
+                )
+            }
+            CsgOperation::Difference => a.clone(),
+        };
+        Self {
+            operation,
+            left,
+            right,
+            bbox,
+        }
+    }
+
+    pub fn union(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        Self::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        Self::new(CsgOperation::Intersection, left, right)
+    }
+
+    /// Cuts `right` out of `left`.
+    pub fn difference(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        Self::new(CsgOperation::Difference, left, right)
+    }
+
+    /// Walks along the whole line of the ray, tracking whether it's inside
+    /// each object, and reports each crossing of the combined surface within
+    /// `ray_t` until `visit` returns false.
+    fn crossings<'a>(
+        &'a self,
+        r: &Ray,
+        ray_t: &Interval,
+        rng: &mut rngs::SmallRng,
+        mut visit: impl FnMut(HitRecord<'a>) -> bool,
+    ) {
+        let left = self.left.hit_all(r, &interval::UNIVERSE, rng);
+        let right = self.right.hit_all(r, &interval::UNIVERSE, rng);
+
+        // The ray starts inside an object if it first crosses its surface on
+        // the way out
+        let mut in_left = left.first().is_some_and(|h| !h.front_face);
+        let mut in_right = right.first().is_some_and(|h| !h.front_face);
+        let mut inside = self.operation.contains(in_left, in_right);
+
+        let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+        loop {
+            let from_left = match (left.peek(), right.peek()) {
+                (Some(a), Some(b)) => a.t <= b.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return,
+            };
+            let mut hitrec = if from_left {
+                let hitrec = left.next().unwrap();
+                in_left = hitrec.front_face;
+                hitrec
+            } else {
+                let hitrec = right.next().unwrap();
+                in_right = hitrec.front_face;
+                hitrec
+            };
+
+            let now_inside = self.operation.contains(in_left, in_right);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+            if hitrec.t > ray_t.max {
+                return;
+            }
+            if ray_t.contains(hitrec.t) {
+                // The normal already faces the ray, but whether the ray is
+                // entering depends on the combined solid rather than the
+                // object it came from
+                hitrec.front_face = now_inside;
+                if !visit(hitrec) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("Csg");
+
+        let mut first = None;
+        self.crossings(r, ray_t, rng, |hitrec| {
+            first = Some(hitrec);
+            false
+        });
+        first
+    }
+
+    fn hit_all(&self, r: &Ray, ray_t: &Interval, rng: &mut rngs::SmallRng) -> Vec<HitRecord> {
+        let mut hits = Vec::new();
+        self.crossings(r, ray_t, rng, |hitrec| {
+            hits.push(hitrec);
+            true
+        });
+        hits
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{
+        interval, material,
+        object::{quad, Hittable, Sphere},
+        ray::Ray,
+        vec3::Vec3,
+        Colour, Point3,
+    };
+
+    use super::Csg;
+
+    fn lambertian(grey: f64) -> Arc<dyn material::Material> {
+        Arc::new(material::Lambertian::from_colour(Colour::new(
+            grey, grey, grey,
+        )))
+    }
+
+    fn sphere(x: f64) -> Arc<Sphere> {
+        Arc::new(Sphere::new(Point3::new(x, 0., 0.), 1., lambertian(0.5)))
+    }
+
+    /// The distances along a ray down the x axis at which it crosses the
+    /// surface, and whether it enters there.
+    fn crossings(object: &dyn Hittable) -> Vec<(f64, bool)> {
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(-10., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+        object
+            .hit_all(&r, &interval::UNIVERSE, &mut rng)
+            .iter()
+            .map(|h| (h.t, h.front_face))
+            .collect()
+    }
+
+    fn assert_crossings(object: &dyn Hittable, expected: &[(f64, bool)]) {
+        let actual = crossings(object);
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for ((t, entering), (expected_t, expected_entering)) in actual.iter().zip(expected) {
+            assert!((t - expected_t).abs() < 1e-9, "{:?}", actual);
+            assert_eq!(entering, expected_entering);
+        }
+    }
+
+    #[test]
+    fn overlapping_spheres() {
+        // Spheres covering -1..1 and 0..2 along the x axis
+        assert_crossings(
+            &Csg::union(sphere(0.), sphere(1.)),
+            &[(9., true), (12., false)],
+        );
+        assert_crossings(
+            &Csg::intersection(sphere(0.), sphere(1.)),
+            &[(10., true), (11., false)],
+        );
+        assert_crossings(
+            &Csg::difference(sphere(0.), sphere(1.)),
+            &[(9., true), (10., false)],
+        );
+
+        let disjoint = Csg::intersection(sphere(0.), sphere(5.));
+        assert!(crossings(&disjoint).is_empty());
+    }
+
+    #[test]
+    fn sphere_with_box_cut_out() {
+        let box_mat = lambertian(0.9);
+        let cutter = Arc::new(quad::new_box(
+            &Point3::new(-0.5, -0.5, -0.5),
+            &Point3::new(0.5, 0.5, 0.5),
+            box_mat.clone(),
+        ));
+        let solid = Csg::difference(sphere(0.), cutter);
+        assert_crossings(
+            &solid,
+            &[(9., true), (9.5, false), (10.5, true), (11., false)],
+        );
+
+        // The inside of the cut takes the box's material, and faces the hole
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(-10., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+        let inner = interval::Interval::new(9.25, f64::INFINITY);
+        let hit = solid.hit(&r, &inner, &mut rng).unwrap();
+        assert!((hit.t - 9.5).abs() < 1e-9);
+        assert!(!hit.front_face);
+        assert!(hit.normal.is_close(&Vec3::new(-1., 0., 0.)));
+        assert!(Arc::ptr_eq(hit.mat, &box_mat));
+
+        let outer = solid.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!(!Arc::ptr_eq(outer.mat, &box_mat));
+    }
+
+    #[test]
+    fn nested_operations() {
+        // A sphere with two others cut out of either side, leaving -0.5..0.5
+        let cut = Csg::difference(
+            Arc::new(Csg::difference(sphere(0.), sphere(-1.5))),
+            sphere(1.5),
+        );
+        assert_crossings(&cut, &[(9.5, true), (10.5, false)]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::{f64::consts, sync::Arc};
+
+use rand::rngs;
+
+use crate::{aabb::AABB, interval::Interval, material, ray::Ray, stats, vec3::Vec3, Point3};
+
+use super::{
+    frame::{self, Frame},
+    HitRecord, Hittable,
+};
+
+/// A cylinder between two points, closed with flat caps unless made
+/// `uncapped`.
+///
+/// On the side, the hit record's u is the angle around the axis and v the
+/// distance along it from the base. The caps are mapped flat onto the unit
+/// square.
+#[derive(Debug)]
+pub struct Cylinder {
+    frame: Frame,
+    height: f64,
+    radius: f64,
+    capped: bool,
+    mat: Arc<dyn material::Material>,
+    aabb: AABB,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, top: Point3, radius: f64, mat: Arc<dyn material::Material>) -> Self {
+        let frame = Frame::new(base, top - base);
+        let height = (top - base).length();
+        let aabb =
+            AABB::from_boxes(&frame.disk_box(0., radius), &frame.disk_box(height, radius)).pad();
+        Self {
+            frame,
+            height,
+            radius,
+            capped: true,
+            mat,
+            aabb,
+        }
+    }
+
+    /// Leaves the ends open, making a tube.
+    pub fn uncapped(mut self) -> Self {
+        self.capped = false;
+        self
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("Cylinder");
+
+        let (o, d) = self.frame.local_ray(r);
+
+        let a = d.x * d.x + d.z * d.z;
+        let half_b = o.x * d.x + o.z * d.z;
+        let c = o.x * o.x + o.z * o.z - self.radius * self.radius;
+        let side = |t: f64| {
+            let p = o + d * t;
+            (0. ..=self.height).contains(&p.y).then(|| {
+                let u = (p.z.atan2(p.x) + consts::PI) / consts::TAU;
+                let normal = Vec3::new(p.x, 0., p.z);
+                (t, (normal, (u, p.y / self.height)))
+            })
+        };
+        let sides = frame::quadratic_roots(a, half_b, c)
+            .map_or([None, None], |(t0, t1)| [side(t0), side(t1)]);
+
+        let cap = |y: f64, normal: f64| {
+            if !self.capped || d.y.abs() <= 1e-12 {
+                return None;
+            }
+            let t = (y - o.y) / d.y;
+            let p = o + d * t;
+            (p.x * p.x + p.z * p.z <= self.radius * self.radius).then(|| {
+                let uv = (0.5 + 0.5 * p.x / self.radius, 0.5 + 0.5 * p.z / self.radius);
+                (t, (Vec3::new(0., normal, 0.), uv))
+            })
+        };
+        let candidates = [sides[0], sides[1], cap(0., -1.), cap(self.height, 1.)];
+
+        let (t, (normal, uv)) = frame::closest(candidates.into_iter().flatten(), ray_t)?;
+        Some(self.frame.hit_record(r, t, normal, uv, &self.mat))
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{interval, material, object::Hittable, ray::Ray, vec3::Vec3, Colour, Point3};
+
+    use super::Cylinder;
+
+    #[test]
+    fn hit_side_and_caps() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        // Lying along the x axis
+        let cylinder = Cylinder::new(Point3::new(-1., 0., 0.), Point3::new(1., 0., 0.), 0.5, mat);
+        let bbox = cylinder.bounding_box();
+        assert!((bbox.x.min + 1.).abs() < 1e-9 && (bbox.z.max - 0.5).abs() < 1e-9);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0.5, 0., 5.), Vec3::new(0., 0., -1.), 0.);
+        let hit = cylinder.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 4.5).abs() < 1e-9);
+        assert!(hit.normal.is_close(&Vec3::new(0., 0., 1.)));
+        assert!((hit.v - 0.75).abs() < 1e-9);
+
+        let r = Ray::new(Point3::new(5., 0.2, 0.), Vec3::new(-1., 0., 0.), 0.);
+        let hit = cylinder.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 4.).abs() < 1e-9);
+        assert!(hit.normal.is_close(&Vec3::new(1., 0., 0.)));
+
+        // Without caps, the ray passes through to the inside of the far wall
+        let tube = Cylinder::new(
+            Point3::new(-1., 0., 0.),
+            Point3::new(1., 0., 0.),
+            0.5,
+            Arc::new(material::Lambertian::from_colour(Colour::zeros())),
+        )
+        .uncapped();
+        let r = Ray::new(Point3::new(5., 0.2, 0.), Vec3::new(-1., 0., 0.), 0.);
+        assert!(tube.hit(&r, &interval::UNIVERSE, &mut rng).is_none());
+        let r = Ray::new(Point3::new(0., 0., 0.), Vec3::new(0., 1., 0.), 0.);
+        let ahead = interval::Interval::new(0.001, f64::INFINITY);
+        let hit = tube.hit(&r, &ahead, &mut rng).unwrap();
+        assert!(!hit.front_face);
+    }
+}
@@ -0,0 +1,128 @@
+use std::{f64::consts, sync::Arc};
+
+use rand::rngs;
+
+use crate::{aabb::AABB, interval::Interval, material, ray::Ray, stats, vec3::Vec3, Point3};
+
+use super::{frame::Frame, HitRecord, Hittable};
+
+/// A flat disk, or an annulus if it has a hole in the middle.
+///
+/// The hit record's u is the angle around the centre and v runs from the
+/// inner edge to the outer edge.
+#[derive(Debug)]
+pub struct Disk {
+    frame: Frame,
+    inner_radius: f64,
+    radius: f64,
+    mat: Arc<dyn material::Material>,
+    aabb: AABB,
+}
+
+impl Disk {
+    /// Creates a disk facing along `normal`.
+    pub fn new(
+        centre: Point3,
+        normal: Vec3,
+        radius: f64,
+        mat: Arc<dyn material::Material>,
+    ) -> Self {
+        Self::annulus(centre, normal, 0., radius, mat)
+    }
+
+    /// Creates a ring facing along `normal`, between two radii.
+    pub fn annulus(
+        centre: Point3,
+        normal: Vec3,
+        inner_radius: f64,
+        radius: f64,
+        mat: Arc<dyn material::Material>,
+    ) -> Self {
+        assert!(
+            0. <= inner_radius && inner_radius < radius,
+            "inner radius must be smaller than the outer radius"
+        );
+        let frame = Frame::new(centre, normal);
+        let aabb = frame.disk_box(0., radius).pad();
+        Self {
+            frame,
+            inner_radius,
+            radius,
+            mat,
+            aabb,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("Disk");
+
+        let (origin, direction) = self.frame.local_ray(r);
+        if direction.y.abs() < 1e-12 {
+            return None;
+        }
+        let t = -origin.y / direction.y;
+        if !ray_t.contains(t) {
+            return None;
+        }
+
+        let p = origin + direction * t;
+        let dist = p.x.hypot(p.z);
+        if dist < self.inner_radius || dist > self.radius {
+            return None;
+        }
+
+        let u = (p.z.atan2(p.x) + consts::PI) / consts::TAU;
+        let v = (dist - self.inner_radius) / (self.radius - self.inner_radius);
+        Some(
+            self.frame
+                .hit_record(r, t, Vec3::new(0., 1., 0.), (u, v), &self.mat),
+        )
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{interval, material, object::Hittable, ray::Ray, vec3::Vec3, Colour, Point3};
+
+    use super::Disk;
+
+    #[test]
+    fn hit_annulus() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let ring = Disk::annulus(
+            Point3::new(0., 0., -2.),
+            Vec3::new(0., 0., 1.),
+            0.5,
+            1.,
+            mat,
+        );
+
+        let bbox = ring.bounding_box();
+        assert!((bbox.x.max - 1.).abs() < 1e-9 && (bbox.y.min + 1.).abs() < 1e-9);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0.75, 0., 0.), Vec3::new(0., 0., -1.), 0.);
+        let hit = ring.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 2.).abs() < 1e-9);
+        assert!(hit.normal.is_close(&Vec3::new(0., 0., 1.)));
+        assert!((hit.v - 0.5).abs() < 1e-9);
+
+        // Through the hole and past the edge
+        for x in [0.25, 1.25] {
+            let r = Ray::new(Point3::new(x, 0., 0.), Vec3::new(0., 0., -1.), 0.);
+            assert!(ring.hit(&r, &interval::UNIVERSE, &mut rng).is_none());
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use crate::{aabb::AABB, interval::Interval, material, ray::Ray, vec3::Vec3, Point3};
+
+use super::HitRecord;
+
+/// An orthonormal frame for shapes defined around an axis, which are
+/// intersected in local coordinates where the axis is +y.
+#[derive(Debug, Clone)]
+pub(crate) struct Frame {
+    origin: Point3,
+    x: Vec3,
+    y: Vec3,
+    z: Vec3,
+}
+
+impl Frame {
+    pub(crate) fn new(origin: Point3, axis: Vec3) -> Self {
+        let y = axis.unit();
+        let helper = if y.x.abs() > 0.9 {
+            Vec3::new(0., 1., 0.)
+        } else {
+            Vec3::new(1., 0., 0.)
+        };
+        let z = helper.cross(y).unit();
+        let x = y.cross(z);
+        Self { origin, x, y, z }
+    }
+
+    pub(crate) fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(v.dot(self.x), v.dot(self.y), v.dot(self.z))
+    }
+
+    pub(crate) fn to_world(&self, v: Vec3) -> Vec3 {
+        self.x * v.x + self.y * v.y + self.z * v.z
+    }
+
+    /// Moves a ray into local coordinates. The direction stays a unit
+    /// vector, so distances along the ray are the same in both frames.
+    pub(crate) fn local_ray(&self, r: &Ray) -> (Point3, Vec3) {
+        (
+            self.to_local(r.origin - self.origin),
+            self.to_local(r.direction),
+        )
+    }
+
+    /// The bounding box of a disk of the given radius centred on the axis at
+    /// height `y`.
+    pub(crate) fn disk_box(&self, y: f64, radius: f64) -> AABB {
+        let centre = self.origin + self.y * y;
+        let extent = Vec3::new(
+            (1. - self.y.x * self.y.x).max(0.).sqrt(),
+            (1. - self.y.y * self.y.y).max(0.).sqrt(),
+            (1. - self.y.z * self.y.z).max(0.).sqrt(),
+        ) * radius;
+        AABB::from_points(centre - extent, centre + extent)
+    }
+
+    /// Builds the hit record for a hit at distance `t`, given the outward
+    /// normal in local coordinates.
+    pub(crate) fn hit_record<'a>(
+        &self,
+        r: &Ray,
+        t: f64,
+        local_normal: Vec3,
+        (u, v): (f64, f64),
+        mat: &'a Arc<dyn material::Material>,
+    ) -> HitRecord<'a> {
+        let outward_normal = self.to_world(local_normal).unit();
+        let mut hitrec = HitRecord::new(r.at(t), outward_normal, t, u, v, mat);
+        hitrec.set_face_normal(r, outward_normal);
+        hitrec
+    }
+}
+
+/// The roots of `a t^2 + 2 half_b t + c` in increasing order.
+pub(crate) fn quadratic_roots(a: f64, half_b: f64, c: f64) -> Option<(f64, f64)> {
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0. || a == 0. {
+        return None;
+    }
+    let sqrt_d = discriminant.sqrt();
+    let (t0, t1) = ((-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a);
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+/// Keeps the candidate hit with the smallest distance inside the interval.
+pub(crate) fn closest<T>(
+    candidates: impl IntoIterator<Item = (f64, T)>,
+    ray_t: &Interval,
+) -> Option<(f64, T)> {
+    candidates
+        .into_iter()
+        .filter(|(t, _)| ray_t.contains(*t))
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+}
@@ -0,0 +1,416 @@
+use std::{io, path::Path, sync::Arc};
+
+use rand::rngs;
+
+use crate::{
+    aabb::AABB,
+    interval::Interval,
+    material,
+    ray::Ray,
+    stats,
+    texture::{ImageTexture, Texture},
+    vec3::Vec3,
+    Point3,
+};
+
+use super::{triangle, HitRecord, Hittable};
+
+/// A terrain surface over a regular grid of height samples, with each cell
+/// split into two triangles.
+///
+/// Rays walk through the grid cell by cell from where they enter, skipping
+/// cells whose range of heights they pass over or under, so the cost grows
+/// with the grid's width rather than its number of cells. Normals are
+/// interpolated from the slope at each sample, and u and v run across the
+/// grid so that an image used for the heights also lines up as a texture.
+#[derive(Debug)]
+pub struct Heightfield {
+    corner: Point3,
+    cell_size: (f64, f64),
+    cols: usize,
+    rows: usize,
+    heights: Vec<f64>,
+    normals: Vec<Vec3>,
+    /// The lowest and highest point of each cell.
+    cell_bounds: Vec<(f64, f64)>,
+    mat: Arc<dyn material::Material>,
+    aabb: AABB,
+}
+
+impl Heightfield {
+    /// Creates a heightfield from `cols` by `rows` samples in row-major order,
+    /// each between 0 and 1. The grid spans `size.x` along x and `size.z`
+    /// along z from `corner`, with the samples scaled up to `size.y` above it.
+    pub fn new(
+        samples: Vec<f64>,
+        cols: usize,
+        rows: usize,
+        corner: Point3,
+        size: Vec3,
+        mat: Arc<dyn material::Material>,
+    ) -> Self {
+        assert!(cols >= 2 && rows >= 2, "expected at least 2x2 samples");
+        assert_eq!(
+            samples.len(),
+            cols * rows,
+            "sample count doesn't match the grid size"
+        );
+
+        let cell_size = (size.x / (cols - 1) as f64, size.z / (rows - 1) as f64);
+        let heights: Vec<f64> = samples.iter().map(|s| corner.y + s * size.y).collect();
+
+        // Estimate the slope at each sample from its neighbours
+        let height = |i: usize, j: usize| heights[j * cols + i];
+        let mut normals = Vec::with_capacity(heights.len());
+        for j in 0..rows {
+            for i in 0..cols {
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(cols - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(rows - 1));
+                let dx = (height(i1, j) - height(i0, j)) / ((i1 - i0) as f64 * cell_size.0);
+                let dz = (height(i, j1) - height(i, j0)) / ((j1 - j0) as f64 * cell_size.1);
+                normals.push(Vec3::new(-dx, 1., -dz).unit());
+            }
+        }
+
+        let mut cell_bounds = Vec::with_capacity((cols - 1) * (rows - 1));
+        for j in 0..rows - 1 {
+            for i in 0..cols - 1 {
+                let corners = [
+                    height(i, j),
+                    height(i + 1, j),
+                    height(i, j + 1),
+                    height(i + 1, j + 1),
+                ];
+                let min = corners.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = corners.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                cell_bounds.push((min, max));
+            }
+        }
+
+        let (min, max) = cell_bounds.iter().fold(
+            (f64::INFINITY, f64::NEG_INFINITY),
+            |(lo, hi), &(min, max)| (lo.min(min), hi.max(max)),
+        );
+        let aabb = AABB::new(
+            Interval::new(corner.x, corner.x + size.x),
+            Interval::new(min, max),
+            Interval::new(corner.z, corner.z + size.z),
+        )
+        .pad();
+
+        Self {
+            corner,
+            cell_size,
+            cols,
+            rows,
+            heights,
+            normals,
+            cell_bounds,
+            mat,
+            aabb,
+        }
+    }
+
+    /// Creates a heightfield by sampling the brightness of a texture on a
+    /// `cols` by `rows` grid. Textures are given u and v across the grid, and
+    /// the point on the grid at the base height, so both image and procedural
+    /// textures such as `NoiseTexture` can be used.
+    pub fn from_texture(
+        texture: &dyn Texture,
+        cols: usize,
+        rows: usize,
+        corner: Point3,
+        size: Vec3,
+        mat: Arc<dyn material::Material>,
+    ) -> Self {
+        let mut samples = Vec::with_capacity(cols * rows);
+        for j in 0..rows {
+            for i in 0..cols {
+                let (u, v) = (i as f64 / (cols - 1) as f64, j as f64 / (rows - 1) as f64);
+                let p = corner + Vec3::new(u * size.x, 0., v * size.z);
+                let colour = texture.get_value(u, v, &p);
+                samples.push((colour.x + colour.y + colour.z) / 3.);
+            }
+        }
+        Self::new(samples, cols, rows, corner, size, mat)
+    }
+
+    /// Loads a heightfield from a greyscale PNG image, with a sample for each
+    /// pixel. The top of the image is at the far edge of the grid along z.
+    pub fn from_image<P>(
+        path: P,
+        corner: Point3,
+        size: Vec3,
+        mat: Arc<dyn material::Material>,
+    ) -> Result<Self, png::DecodingError>
+    where
+        P: AsRef<Path>,
+    {
+        let image = ImageTexture::open(path)?;
+        let (cols, rows) = (image.width(), image.height());
+        if cols < 2 || rows < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "heightfield image must be at least 2 by 2 pixels",
+            )
+            .into());
+        }
+        Ok(Self::from_texture(&image, cols, rows, corner, size, mat))
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Point3 {
+        Point3::new(
+            self.corner.x + i as f64 * self.cell_size.0,
+            self.heights[j * self.cols + i],
+            self.corner.z + j as f64 * self.cell_size.1,
+        )
+    }
+
+    fn uv(&self, i: usize, j: usize) -> (f64, f64) {
+        (
+            i as f64 / (self.cols - 1) as f64,
+            j as f64 / (self.rows - 1) as f64,
+        )
+    }
+
+    /// Intersects the two triangles of a cell, keeping the closer hit.
+    fn hit_cell<'a>(
+        &'a self,
+        r: &Ray,
+        ray_t: &Interval,
+        i: usize,
+        j: usize,
+    ) -> Option<HitRecord<'a>> {
+        let corners = [(i, j), (i, j + 1), (i + 1, j + 1), (i + 1, j)];
+        let mut closest = None;
+        let mut closest_so_far = ray_t.max;
+        for triangle in [[0, 1, 2], [0, 2, 3]] {
+            let [a, b, c] = triangle.map(|k| self.vertex(corners[k].0, corners[k].1));
+            let interval = Interval::new(ray_t.min, closest_so_far);
+            if let Some((t, b1, b2)) = triangle::intersect(r, &interval, a, b, c) {
+                closest_so_far = t;
+                closest = Some((triangle, t, b1, b2));
+            }
+        }
+
+        let (triangle, t, b1, b2) = closest?;
+        let vertices = triangle.map(|k| corners[k]);
+        let [a, b, c] = vertices.map(|(i, j)| self.vertex(i, j));
+        Some(triangle::surface_hit(
+            r,
+            t,
+            (b1, b2),
+            (b - a).cross(c - a),
+            Some(vertices.map(|(i, j)| self.normals[j * self.cols + i])),
+            Some(vertices.map(|(i, j)| self.uv(i, j))),
+            &self.mat,
+        ))
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("Heightfield");
+
+        let (start, end) = self.aabb.clip(r, ray_t)?;
+        let (cells_x, cells_z) = (self.cols - 1, self.rows - 1);
+
+        // The cell the ray starts in, and the distances to the next cell
+        // boundary along each axis
+        let entry = r.at(start);
+        let cell = |offset: f64, size: f64, count: usize| {
+            ((offset / size).floor().max(0.) as usize).min(count - 1)
+        };
+        let mut i = cell(entry.x - self.corner.x, self.cell_size.0, cells_x);
+        let mut j = cell(entry.z - self.corner.z, self.cell_size.1, cells_z);
+        let boundary = |index: usize, origin: f64, corner: f64, size: f64, d: f64| {
+            if d == 0. {
+                return (f64::INFINITY, f64::INFINITY);
+            }
+            let next = index + usize::from(d > 0.);
+            ((corner + next as f64 * size - origin) / d, size / d.abs())
+        };
+        let (mut next_x, delta_x) = boundary(
+            i,
+            r.origin.x,
+            self.corner.x,
+            self.cell_size.0,
+            r.direction.x,
+        );
+        let (mut next_z, delta_z) = boundary(
+            j,
+            r.origin.z,
+            self.corner.z,
+            self.cell_size.1,
+            r.direction.z,
+        );
+
+        let mut enter = start;
+        loop {
+            let exit = next_x.min(next_z).min(end);
+
+            // Only test the triangles if the ray passes through the cell's
+            // range of heights
+            let (min, max) = self.cell_bounds[j * cells_x + i];
+            let (y0, y1) = (r.at(enter).y, r.at(exit).y);
+            let tolerance = 1e-9 * (1. + max.abs());
+            if y0.min(y1) <= max + tolerance && y0.max(y1) >= min - tolerance {
+                if let Some(hitrec) = self.hit_cell(r, ray_t, i, j) {
+                    return Some(hitrec);
+                }
+            }
+
+            if exit >= end {
+                return None;
+            }
+            enter = exit;
+            if next_x < next_z {
+                if r.direction.x > 0. && i + 1 < cells_x {
+                    i += 1;
+                } else if r.direction.x < 0. && i > 0 {
+                    i -= 1;
+                } else {
+                    return None;
+                }
+                next_x += delta_x;
+            } else {
+                if r.direction.z > 0. && j + 1 < cells_z {
+                    j += 1;
+                } else if r.direction.z < 0. && j > 0 {
+                    j -= 1;
+                } else {
+                    return None;
+                }
+                next_z += delta_z;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, fs::File, sync::Arc};
+
+    use rand::{rngs, Rng, SeedableRng};
+
+    use crate::{
+        interval, material,
+        object::Hittable,
+        ray::Ray,
+        texture::{ImageTexture, NoiseTexture},
+        vec3::Vec3,
+        Colour, Point3,
+    };
+
+    use super::Heightfield;
+
+    #[test]
+    fn sloped_plane() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        // A ramp rising from 0 to 2 along x over a 4x4 square
+        let (cols, rows) = (9, 5);
+        let samples = (0..cols * rows)
+            .map(|k| (k % cols) as f64 / (cols - 1) as f64)
+            .collect();
+        let ramp = Heightfield::new(
+            samples,
+            cols,
+            rows,
+            Point3::zeros(),
+            Vec3::new(4., 2., 4.),
+            mat,
+        );
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let expected_normal = Vec3::new(-1., 2., 0.).unit();
+        for _ in 0..100 {
+            let (x, z) = (rng.gen_range(0.01..3.99), rng.gen_range(0.01..3.99));
+            let r = Ray::new(Point3::new(x, 5., z), Vec3::new(0., -1., 0.), 0.);
+            let hit = ramp.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+            assert!(hit.point.is_close(&Point3::new(x, x / 2., z)));
+            assert!(hit.normal.is_close(&expected_normal));
+            assert!(hit.front_face);
+            assert!((hit.u - x / 4.).abs() < 1e-9 && (hit.v - z / 4.).abs() < 1e-9);
+        }
+
+        // Grazing rays cross many cells before hitting the rising slope, and
+        // miss if they pass over the top
+        let r = Ray::new(Point3::new(-1., 1., 1.5), Vec3::new(1., 0., 0.3), 0.);
+        let hit = ramp.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.point.y - 1.).abs() < 1e-9 && (hit.point.x - 2.).abs() < 1e-9);
+        let r = Ray::new(Point3::new(-1., 2.5, 1.5), Vec3::new(1., 0., 0.3), 0.);
+        assert!(ramp.hit(&r, &interval::UNIVERSE, &mut rng).is_none());
+
+        // Heading back down the slope from above the high end
+        let r = Ray::new(Point3::new(5., 4., 3.), Vec3::new(-1., -1.5, -0.2), 0.);
+        let hit = ramp.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.point.y - hit.point.x / 2.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn from_textures() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        // A 3x2 image with a bright pixel at the top right, which is the far
+        // corner along x and z
+        let image = ImageTexture::from_pixels(3, 2, 1, vec![0, 0, 255, 0, 0, 0]);
+        let field = Heightfield::from_texture(
+            &image,
+            3,
+            2,
+            Point3::zeros(),
+            Vec3::new(2., 1., 1.),
+            mat.clone(),
+        );
+        assert!((field.vertex(2, 1).y - 1.).abs() < 1e-12);
+        assert_eq!(field.vertex(2, 0).y, 0.);
+        assert_eq!(field.vertex(0, 1).y, 0.);
+
+        let noise = NoiseTexture::with_scale(0.1);
+        let terrain = Heightfield::from_texture(
+            &noise,
+            32,
+            32,
+            Point3::new(-10., 0., -10.),
+            Vec3::new(20., 3., 20.),
+            mat,
+        );
+        let bbox = terrain.bounding_box();
+        assert!(bbox.y.min >= 0. && bbox.y.max <= 3.);
+
+        // Every ray straight down lands on the terrain
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let (x, z) = (rng.gen_range(-9.9..9.9), rng.gen_range(-9.9..9.9));
+            let r = Ray::new(Point3::new(x, 10., z), Vec3::new(0., -1., 0.), 0.);
+            let hit = terrain.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+            assert!((hit.point.x - x).abs() < 1e-9 && (hit.point.z - z).abs() < 1e-9);
+            assert!(hit.normal.y > 0.);
+        }
+    }
+
+    #[test]
+    fn reject_single_row_image() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let path =
+            std::env::temp_dir().join(format!("lumiere-heightfield-{}.png", std::process::id()));
+        let mut encoder = png::Encoder::new(File::create(&path).unwrap(), 3, 1);
+        encoder.set_color(png::ColorType::Grayscale);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0, 128, 255]).unwrap();
+        drop(writer);
+
+        let field = Heightfield::from_image(&path, Point3::zeros(), Vec3::new(2., 1., 1.), mat);
+        fs::remove_file(&path).unwrap();
+        assert!(field.is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use rand::{rngs, Rng};
+
+use crate::{interval, material, stats, texture, vec3::Vec3, Colour};
+
+use super::{HitRecord, Hittable};
+
+/// A participating medium whose density varies through space, such as a
+/// cloud or a plume of smoke. Scattering events are sampled with delta
+/// tracking against the maximum density of the field. Like `ConstantMedium`,
+/// priorities don't apply, so overlapping media add their densities.
+#[derive(Debug)]
+pub struct HeterogeneousMedium {
+    phase_function: Arc<dyn material::Material>,
+    boundary: Arc<dyn Hittable>,
+    density: Arc<dyn texture::DensityField>,
+    max_density: f64,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(
+        boundary: Arc<dyn Hittable>,
+        density: Arc<dyn texture::DensityField>,
+        texture: Arc<dyn texture::Texture>,
+    ) -> Self {
+        Self {
+            phase_function: Arc::new(material::Isotropic::new(texture)),
+            boundary,
+            max_density: density.max_density(),
+            density,
+        }
+    }
+
+    pub fn from_colour(
+        boundary: Arc<dyn Hittable>,
+        density: Arc<dyn texture::DensityField>,
+        colour: Colour,
+    ) -> Self {
+        Self {
+            phase_function: Arc::new(material::Isotropic::from_colour(colour)),
+            boundary,
+            max_density: density.max_density(),
+            density,
+        }
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(
+        &self,
+        r: &crate::ray::Ray,
+        ray_t: &crate::interval::Interval,
+        rng: &mut rngs::SmallRng,
+    ) -> Option<super::HitRecord> {
+        stats::record_hit("HeterogeneousMedium");
+
+        if self.max_density <= 0. {
+            return None;
+        }
+
+        let mut hitrec1 = self.boundary.hit(r, &interval::UNIVERSE, rng)?;
+        let mut hitrec2 = self.boundary.hit(
+            r,
+            &interval::Interval::new(hitrec1.t + 0.0001, f64::INFINITY),
+            rng,
+        )?;
+
+        if hitrec1.t < ray_t.min {
+            hitrec1.t = ray_t.min;
+        }
+        if hitrec2.t > ray_t.max {
+            hitrec2.t = ray_t.max;
+        }
+
+        if hitrec1.t >= hitrec2.t {
+            return None;
+        }
+
+        if hitrec1.t < 0. {
+            hitrec1.t = 0.;
+        }
+
+        // Take exponentially distributed steps through a homogeneous medium
+        // with the maximum density, accepting each step as a real collision
+        // with probability proportional to the density at that point.
+        let ray_length = r.direction.length();
+        let mut t = hitrec1.t;
+        loop {
+            let step = material::free_flight_distance(self.max_density, rng);
+            t += step / ray_length;
+            if t >= hitrec2.t {
+                return None;
+            }
+
+            let point = r.at(t);
+            if rng.gen::<f64>() * self.max_density < self.density.density(&point) {
+                return Some(HitRecord::new(
+                    point,
+                    Vec3::new(1., 0., 0.),
+                    t,
+                    0.,
+                    0.,
+                    &self.phase_function,
+                ));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> &crate::aabb::AABB {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{
+        aabb::AABB, interval, material, object::Hittable, object::Sphere, ray::Ray, texture,
+        vec3::Vec3, Colour, Point3,
+    };
+
+    use super::HeterogeneousMedium;
+
+    #[test]
+    fn uniform_field_matches_beer_lambert() {
+        let boundary = Arc::new(Sphere::new(
+            Point3::new(0., 0., 0.),
+            1.,
+            Arc::new(material::Lambertian::from_colour(Colour::new(1., 1., 1.))),
+        ));
+        let bounds = AABB::from_points(Point3::new(-1., -1., -1.), Point3::new(1., 1., 1.));
+        let density = Arc::new(texture::VoxelGrid::new(bounds, [1, 1, 1], vec![0.5]));
+        let medium = HeterogeneousMedium::from_colour(boundary, density, Colour::new(1., 1., 1.));
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0., 0., -5.), Vec3::new(0., 0., 1.), 0.);
+        let trials = 20000;
+        let hits = (0..trials)
+            .filter(|_| medium.hit(&r, &interval::UNIVERSE, &mut rng).is_some())
+            .count();
+
+        // The ray travels a distance of 2 through a density of 0.5
+        let expected = 1. - (-1_f64).exp();
+        let fraction = hits as f64 / trials as f64;
+        assert!((fraction - expected).abs() < 0.02, "{}", fraction);
+    }
+}
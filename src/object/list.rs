@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use rand::rngs;
 
-use crate::{aabb::AABB, interval, ray};
+use crate::{aabb::AABB, interval, ray, stats};
 
 use super::Hittable;
 
@@ -43,6 +43,8 @@ impl Hittable for HittableList {
         ray_t: &interval::Interval,
         rng: &mut rngs::SmallRng,
     ) -> Option<super::HitRecord> {
+        stats::record_hit("HittableList");
+
         let mut closest_so_far = ray_t.max;
         let mut hitrec = None;
         for object in &self.objects {
@@ -59,4 +61,8 @@ impl Hittable for HittableList {
     fn bounding_box(&self) -> &AABB {
         &self.bbox
     }
+
+    fn children(&self) -> Vec<&Arc<dyn Hittable>> {
+        self.objects.iter().collect()
+    }
 }
@@ -0,0 +1,324 @@
+use std::sync::Arc;
+
+use rand::rngs;
+
+use crate::{
+    aabb::AABB,
+    interval::{self, Interval},
+    material,
+    ray::Ray,
+    stats,
+    vec3::Vec3,
+    Colour, Point3,
+};
+
+use super::{triangle, HitRecord, Hittable};
+
+/// Maximum number of triangles in a leaf of the mesh's BVH.
+const LEAF_SIZE: usize = 4;
+
+/// A node of the flattened BVH over a mesh's triangles.
+#[derive(Debug)]
+struct MeshNode {
+    bbox: AABB,
+    /// For leaves, the index of the first triangle. For interior nodes, the
+    /// index of the second child, where the first child directly follows the
+    /// node.
+    offset: usize,
+    /// The number of triangles in a leaf, or zero for interior nodes.
+    count: usize,
+}
+
+/// A triangle mesh sharing vertices between triangles, with optional
+/// per-vertex normals and texture coordinates.
+///
+/// The triangles are stored as indices into the vertex arrays along with a
+/// BVH of their own, so a mesh is a single hittable however many triangles
+/// it has. Without texture coordinates, the hit record's u and v are the
+/// barycentric coordinates of the hit.
+#[derive(Debug)]
+pub struct TriangleMesh {
+    positions: Vec<Point3>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f64, f64)>>,
+    colours: Option<Vec<Colour>>,
+    indices: Vec<[usize; 3]>,
+    mat: Arc<dyn material::Material>,
+    nodes: Vec<MeshNode>,
+    aabb: AABB,
+}
+
+impl TriangleMesh {
+    /// Creates a mesh from vertex positions and triangles given as triples
+    /// of vertex indices.
+    pub fn new(
+        positions: Vec<Point3>,
+        indices: Vec<[usize; 3]>,
+        mat: Arc<dyn material::Material>,
+    ) -> Self {
+        assert!(
+            indices.iter().flatten().all(|&i| i < positions.len()),
+            "triangle vertex index out of range"
+        );
+
+        let mut mesh = Self {
+            positions,
+            normals: None,
+            uvs: None,
+            colours: None,
+            indices,
+            mat,
+            nodes: Vec::new(),
+            aabb: AABB::new(interval::EMPTY, interval::EMPTY, interval::EMPTY),
+        };
+        mesh.build_bvh();
+        if let Some(root) = mesh.nodes.first() {
+            mesh.aabb = root.bbox.clone();
+        }
+        mesh
+    }
+
+    /// Sets a normal for each vertex, interpolated across the triangles for
+    /// smooth shading.
+    pub fn with_normals(mut self, normals: Vec<Vec3>) -> Self {
+        assert_eq!(
+            normals.len(),
+            self.positions.len(),
+            "expected a normal per vertex"
+        );
+        self.normals = Some(normals);
+        self
+    }
+
+    /// Sets texture coordinates for each vertex.
+    pub fn with_uvs(mut self, uvs: Vec<(f64, f64)>) -> Self {
+        assert_eq!(uvs.len(), self.positions.len(), "expected a uv per vertex");
+        self.uvs = Some(uvs);
+        self
+    }
+
+    /// Sets a colour for each vertex, interpolated across the triangles and
+    /// passed to the material with each hit. Shade the mesh with a
+    /// [`VertexColourTexture`](crate::texture::VertexColourTexture) to use
+    /// them.
+    pub fn with_colours(mut self, colours: Vec<Colour>) -> Self {
+        assert_eq!(
+            colours.len(),
+            self.positions.len(),
+            "expected a colour per vertex"
+        );
+        self.colours = Some(colours);
+        self
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn triangle_bbox(&self, triangle: &[usize; 3]) -> AABB {
+        let [a, b, c] = triangle.map(|i| self.positions[i]);
+        AABB::from_boxes(&AABB::from_points(a, b), &AABB::from_points(a, c))
+    }
+
+    fn centroid(&self, triangle: &[usize; 3]) -> Point3 {
+        let [a, b, c] = triangle.map(|i| self.positions[i]);
+        (a + b + c) / 3.
+    }
+
+    /// Builds the BVH, reordering the triangles so that each leaf refers to a
+    /// contiguous range of them.
+    fn build_bvh(&mut self) {
+        self.nodes.clear();
+        let mut indices = std::mem::take(&mut self.indices);
+        if !indices.is_empty() {
+            self.build_node(&mut indices, 0);
+        }
+        self.indices = indices;
+    }
+
+    fn build_node(&mut self, triangles: &mut [[usize; 3]], start: usize) -> usize {
+        let bbox = triangles
+            .iter()
+            .map(|t| self.triangle_bbox(t))
+            .reduce(|a, b| AABB::from_boxes(&a, &b))
+            .unwrap()
+            .pad();
+
+        let node = self.nodes.len();
+        self.nodes.push(MeshNode {
+            bbox,
+            offset: start,
+            count: triangles.len(),
+        });
+        if triangles.len() <= LEAF_SIZE {
+            return node;
+        }
+
+        // Split at the median along the longest axis of the centroids
+        let centroids = triangles
+            .iter()
+            .map(|t| {
+                let c = self.centroid(t);
+                AABB::from_points(c, c)
+            })
+            .reduce(|a, b| AABB::from_boxes(&a, &b))
+            .unwrap();
+        let axis = (0..3)
+            .max_by(|&a, &b| {
+                centroids
+                    .axis(a)
+                    .size()
+                    .total_cmp(&centroids.axis(b).size())
+            })
+            .unwrap();
+        triangles
+            .sort_unstable_by(|a, b| self.centroid(a)[axis].total_cmp(&self.centroid(b)[axis]));
+
+        let mid = triangles.len() / 2;
+        let (left, right) = triangles.split_at_mut(mid);
+        self.build_node(left, start);
+        let second = self.build_node(right, start + mid);
+        self.nodes[node].offset = second;
+        self.nodes[node].count = 0;
+        node
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("TriangleMesh");
+
+        let mut closest = None;
+        let mut closest_so_far = ray_t.max;
+
+        let mut stack = [0; 64];
+        let mut len = usize::from(!self.nodes.is_empty());
+        while len > 0 {
+            len -= 1;
+            let index = stack[len];
+            let node = &self.nodes[index];
+            if !node.bbox.hit(r, &Interval::new(ray_t.min, closest_so_far)) {
+                continue;
+            }
+
+            if node.count == 0 {
+                stats::record_bvh_node();
+                stack[len] = node.offset;
+                stack[len + 1] = index + 1;
+                len += 2;
+                continue;
+            }
+
+            for i in node.offset..node.offset + node.count {
+                let [a, b, c] = self.indices[i].map(|v| self.positions[v]);
+                let interval = Interval::new(ray_t.min, closest_so_far);
+                if let Some((t, b1, b2)) = triangle::intersect(r, &interval, a, b, c) {
+                    closest_so_far = t;
+                    closest = Some((i, t, b1, b2));
+                }
+            }
+        }
+
+        let (i, t, b1, b2) = closest?;
+        let triangle = self.indices[i];
+        let [a, b, c] = triangle.map(|v| self.positions[v]);
+        let mut hitrec = triangle::surface_hit(
+            r,
+            t,
+            (b1, b2),
+            (b - a).cross(c - a),
+            self.normals.as_ref().map(|n| triangle.map(|v| n[v])),
+            self.uvs.as_ref().map(|uv| triangle.map(|v| uv[v])),
+            &self.mat,
+        );
+        hitrec.colour = self.colours.as_ref().map(|colours| {
+            let [c0, c1, c2] = triangle.map(|v| colours[v]);
+            c0 * (1. - b1 - b2) + c1 * b1 + c2 * b2
+        });
+        Some(hitrec)
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, Rng, SeedableRng};
+
+    use crate::{interval, material, object::Hittable, ray::Ray, vec3::Vec3, Colour, Point3};
+
+    use super::TriangleMesh;
+
+    /// A flat grid of squares in the xy plane, each split into two triangles.
+    fn grid(size: usize) -> TriangleMesh {
+        let mut positions = Vec::new();
+        for y in 0..=size {
+            for x in 0..=size {
+                positions.push(Point3::new(x as f64, y as f64, 0.));
+            }
+        }
+        let mut indices = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                let i = y * (size + 1) + x;
+                indices.push([i, i + 1, i + size + 2]);
+                indices.push([i, i + size + 2, i + size + 1]);
+            }
+        }
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        TriangleMesh::new(positions, indices, mat)
+    }
+
+    #[test]
+    fn hit_every_cell() {
+        let mesh = grid(10);
+        assert_eq!(mesh.triangle_count(), 200);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(1);
+        for _ in 0..200 {
+            let x = rng.gen_range(0.01..9.99);
+            let y = rng.gen_range(0.01..9.99);
+            let r = Ray::new(Point3::new(x, y, 1.), Vec3::new(0., 0., -1.), 0.);
+            let hit = mesh.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+            assert!(hit.point.is_close(&Point3::new(x, y, 0.)));
+        }
+
+        let r = Ray::new(Point3::new(11., 5., 1.), Vec3::new(0., 0., -1.), 0.);
+        assert!(mesh.hit(&r, &interval::UNIVERSE, &mut rng).is_none());
+    }
+
+    #[test]
+    fn closest_of_overlapping_triangles() {
+        let positions = vec![
+            Point3::new(0., 0., 0.),
+            Point3::new(1., 0., 0.),
+            Point3::new(0., 1., 0.),
+            Point3::new(0., 0., 1.),
+            Point3::new(1., 0., 1.),
+            Point3::new(0., 1., 1.),
+        ];
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let mesh = TriangleMesh::new(positions, vec![[0, 1, 2], [3, 4, 5]], mat).with_uvs(vec![
+            (0., 0.),
+            (1., 0.),
+            (0., 1.),
+            (0.5, 0.5),
+            (0.5, 0.5),
+            (0.5, 0.5),
+        ]);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0.2, 0.2, 2.), Vec3::new(0., 0., -1.), 0.);
+        let hit = mesh.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 1.).abs() < 1e-12);
+        assert_eq!((hit.u, hit.v), (0.5, 0.5));
+    }
+}
@@ -1,17 +1,42 @@
+pub mod capsule;
+pub mod cone;
 pub mod constant_medium;
+pub mod csg;
+pub mod cylinder;
+pub mod disk;
+mod frame;
+pub mod heightfield;
+pub mod heterogeneous_medium;
 pub mod list;
+pub mod mesh;
 pub mod moving_sphere;
 pub mod object;
 pub mod quad;
 pub mod rotate;
+pub mod sdf;
 pub mod sphere;
+pub mod torus;
+pub mod transform;
 pub mod translate;
+pub mod triangle;
 
+pub use capsule::Capsule;
+pub use cone::Cone;
 pub use constant_medium::ConstantMedium;
+pub use csg::{Csg, CsgOperation};
+pub use cylinder::Cylinder;
+pub use disk::Disk;
+pub use heightfield::Heightfield;
+pub use heterogeneous_medium::HeterogeneousMedium;
 pub use list::HittableList;
+pub use mesh::TriangleMesh;
 pub use moving_sphere::MovingSphere;
 pub use object::{HitRecord, Hittable};
 pub use quad::Quad;
 pub use rotate::RotateY;
+pub use sdf::Sdf;
 pub use sphere::Sphere;
+pub use torus::Torus;
+pub use transform::Transform;
 pub use translate::Translate;
+pub use triangle::Triangle;
@@ -2,7 +2,7 @@ use std::{f64::consts, sync::Arc};
 
 use rand::rngs;
 
-use crate::{aabb::AABB, interval, material, ray::Ray, vec3::Vec3, Point3};
+use crate::{aabb::AABB, interval, material, ray::Ray, stats, vec3::Vec3, Point3};
 
 use super::object;
 
@@ -66,6 +66,8 @@ impl object::Hittable for MovingSphere {
         ray_t: &interval::Interval,
         _rng: &mut rngs::SmallRng,
     ) -> Option<object::HitRecord> {
+        stats::record_hit("MovingSphere");
+
         let oc = r.origin - self.centre(r.time);
         let a = r.direction.length_squared();
         let half_b = oc.dot(r.direction);
@@ -2,7 +2,7 @@ use std::{fmt, sync::Arc};
 
 use rand::rngs;
 
-use crate::{aabb::AABB, interval, material, ray::Ray, vec3::Vec3, Point3};
+use crate::{aabb::AABB, interval, material, ray::Ray, vec3::Vec3, Colour, Point3};
 
 #[derive(Debug)]
 pub struct HitRecord<'a> {
@@ -13,6 +13,9 @@ pub struct HitRecord<'a> {
     pub v: f64,
     pub front_face: bool,
     pub mat: &'a Arc<dyn material::Material>,
+    /// The colour interpolated from the vertex colours of a mesh, if it has
+    /// them.
+    pub colour: Option<Colour>,
 }
 
 impl<'a> HitRecord<'a> {
@@ -32,6 +35,7 @@ impl<'a> HitRecord<'a> {
             v,
             front_face: false,
             mat,
+            colour: None,
         }
     }
 
@@ -53,4 +57,42 @@ pub trait Hittable: fmt::Debug + Send + Sync {
         rng: &mut rngs::SmallRng,
     ) -> Option<HitRecord>;
     fn bounding_box(&self) -> &AABB;
+
+    /// The objects that this one groups together, such as the members of a
+    /// list, whose closest hit is always the group's hit. Other objects have
+    /// none.
+    fn children(&self) -> Vec<&Arc<dyn Hittable>> {
+        Vec::new()
+    }
+
+    /// Finds every time the ray crosses the surface within `ray_t`, in
+    /// order. For a closed object, the crossings alternate between entering
+    /// and leaving it, as given by each record's `front_face`.
+    ///
+    /// By default this repeatedly calls `hit`, starting each search just past
+    /// the previous crossing.
+    fn hit_all(
+        &self,
+        r: &Ray,
+        ray_t: &interval::Interval,
+        rng: &mut rngs::SmallRng,
+    ) -> Vec<HitRecord> {
+        let mut hits = Vec::new();
+        let mut min = ray_t.min;
+        while hits.len() < MAX_CROSSINGS {
+            let Some(hitrec) = self.hit(r, &interval::Interval::new(min, ray_t.max), rng) else {
+                break;
+            };
+            min = hitrec.t + CROSSING_EPSILON * hitrec.t.abs().max(1.);
+            hits.push(hitrec);
+        }
+        hits
+    }
 }
+
+/// The most surface crossings `Hittable::hit_all` looks for by default.
+const MAX_CROSSINGS: usize = 64;
+
+/// How far past a crossing `Hittable::hit_all` starts searching for the next
+/// one by default, relative to the distance along the ray.
+const CROSSING_EPSILON: f64 = 1e-9;
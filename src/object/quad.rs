@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use rand::rngs;
 
-use crate::{aabb::AABB, material, object, vec3::Vec3, Point3};
+use crate::{aabb::AABB, material, object, stats, vec3::Vec3, Point3};
 
 use super::{Hittable, HittableList};
 
@@ -45,6 +45,8 @@ impl Hittable for Quad {
         ray_t: &crate::interval::Interval,
         _rng: &mut rngs::SmallRng,
     ) -> Option<super::HitRecord> {
+        stats::record_hit("Quad");
+
         let denom = self.normal.dot(r.direction.unit());
 
         // If denominator is effectively zero, then the ray is parallel to the
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use rand::rngs;
 
-use crate::{aabb::AABB, ray::Ray, vec3::Vec3, Point3};
+use crate::{aabb::AABB, ray::Ray, stats, vec3::Vec3, Point3};
 
 use super::Hittable;
 
@@ -61,6 +61,8 @@ impl Hittable for RotateY {
         ray_t: &crate::interval::Interval,
         rng: &mut rngs::SmallRng,
     ) -> Option<super::HitRecord> {
+        stats::record_hit("RotateY");
+
         // Change the ray from world space to object space
         let mut origin = r.origin;
         let mut direction = r.direction;
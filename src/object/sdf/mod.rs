@@ -0,0 +1,174 @@
+//! Objects defined by signed distance functions and rendered by sphere
+//! tracing, for procedural shapes that have no simple ray intersection.
+
+pub mod operations;
+pub mod shapes;
+
+use std::{f64::consts, fmt, sync::Arc};
+
+use rand::rngs;
+
+use crate::{aabb::AABB, interval::Interval, material, ray::Ray, stats, vec3::Vec3, Point3};
+
+use super::{HitRecord, Hittable};
+
+pub use operations::{Offset, Repeat, SmoothSubtraction, SmoothUnion};
+pub use shapes::{Mandelbulb, RoundedBox, SdfBox, SdfSphere, SdfTorus};
+
+/// A signed distance function, giving the distance from a point to the
+/// nearest surface, negative inside the shape.
+///
+/// The distance may be underestimated, which only slows tracing, but must
+/// never be overestimated or the tracer can step through the surface.
+pub trait DistanceField: fmt::Debug + Send + Sync {
+    fn distance(&self, p: &Point3) -> f64;
+
+    /// A box containing the whole surface.
+    fn bounding_box(&self) -> AABB;
+}
+
+/// A hittable surface where a distance field is zero, found by sphere
+/// tracing: stepping along the ray by the distance to the nearest surface,
+/// which can never step past it.
+///
+/// Normals come from the gradient of the field, and the hit record's u and v
+/// are the spherical coordinates of the normal.
+#[derive(Debug)]
+pub struct Sdf {
+    field: Arc<dyn DistanceField>,
+    mat: Arc<dyn material::Material>,
+    max_steps: usize,
+    epsilon: f64,
+    aabb: AABB,
+}
+
+impl Sdf {
+    pub fn new(field: Arc<dyn DistanceField>, mat: Arc<dyn material::Material>) -> Self {
+        let aabb = field.bounding_box().pad();
+        Self {
+            field,
+            mat,
+            max_steps: 256,
+            epsilon: 1e-5,
+            aabb,
+        }
+    }
+
+    /// Sets the most steps taken along a ray before giving up, which
+    /// defaults to 256. Detailed fractals need more.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Sets how close to the surface counts as a hit, which defaults to
+    /// 1e-5.
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// Estimates the gradient of the field by central differences.
+    fn gradient(&self, p: Point3) -> Vec3 {
+        let h = self.epsilon;
+        let d =
+            |offset: Vec3| self.field.distance(&(p + offset)) - self.field.distance(&(p - offset));
+        Vec3::new(
+            d(Vec3::new(h, 0., 0.)),
+            d(Vec3::new(0., h, 0.)),
+            d(Vec3::new(0., 0., h)),
+        )
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("Sdf");
+
+        let (mut t, end) = self.aabb.clip(r, ray_t)?;
+
+        // Rays starting inside the shape trace the distance to the way out
+        let sign = self.field.distance(&r.at(t)).signum();
+        let mut hit = false;
+        for _ in 0..self.max_steps {
+            let d = sign * self.field.distance(&r.at(t));
+            if d < self.epsilon {
+                hit = true;
+                break;
+            }
+            t += d;
+            if t > end {
+                return None;
+            }
+        }
+        if !hit || !ray_t.contains(t) {
+            return None;
+        }
+
+        let point = r.at(t);
+        let outward_normal = self.gradient(point).unit();
+        if outward_normal.near_zero() || outward_normal.x.is_nan() {
+            return None;
+        }
+        let u = ((-outward_normal.z).atan2(outward_normal.x) + consts::PI) / consts::TAU;
+        let v = (-outward_normal.y).acos() / consts::PI;
+        let mut hitrec = HitRecord::new(point, outward_normal, t, u, v, &self.mat);
+        hitrec.set_face_normal(r, outward_normal);
+        Some(hitrec)
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{
+        interval, material,
+        object::{Hittable, Sphere},
+        ray::Ray,
+        vec3::Vec3,
+        Colour, Point3,
+    };
+
+    use super::{Sdf, SdfSphere};
+
+    #[test]
+    fn matches_analytic_sphere() {
+        let mat: Arc<dyn material::Material> = Arc::new(material::Lambertian::from_colour(
+            Colour::new(0.5, 0.5, 0.5),
+        ));
+        let centre = Point3::new(0.3, -0.2, -4.);
+        let traced = Sdf::new(Arc::new(SdfSphere::new(centre, 1.5)), mat.clone());
+        let analytic = Sphere::new(centre, 1.5, mat);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let ahead = interval::Interval::new(0.001, f64::INFINITY);
+        for direction in [
+            Vec3::new(0., 0., -1.),
+            Vec3::new(0.2, 0.1, -1.),
+            Vec3::new(0.1, -0.1, -1.),
+        ] {
+            let r = Ray::new(Point3::zeros(), direction, 0.);
+            let expected = analytic.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+            let hit = traced.hit(&r, &ahead, &mut rng).unwrap();
+            assert!((hit.t - expected.t).abs() < 1e-4);
+            assert!((hit.normal - expected.normal).length() < 1e-4);
+            assert!(hit.front_face);
+        }
+
+        // Tracing out from the centre finds the inside of the surface
+        let r = Ray::new(centre, Vec3::new(1., 0., 0.), 0.);
+        let hit = traced.hit(&r, &ahead, &mut rng).unwrap();
+        assert!((hit.t - 1.5).abs() < 1e-4);
+        assert!(!hit.front_face);
+
+        let r = Ray::new(Point3::zeros(), Vec3::new(1., 0., -1.), 0.);
+        assert!(traced.hit(&r, &interval::UNIVERSE, &mut rng).is_none());
+    }
+}
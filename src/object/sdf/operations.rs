@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use crate::{aabb::AABB, vec3::Vec3, Point3};
+
+use super::DistanceField;
+
+/// Blends two shapes together, rounding the join over a distance of about
+/// `smoothing`.
+#[derive(Debug)]
+pub struct SmoothUnion {
+    a: Arc<dyn DistanceField>,
+    b: Arc<dyn DistanceField>,
+    smoothing: f64,
+}
+
+impl SmoothUnion {
+    pub fn new(a: Arc<dyn DistanceField>, b: Arc<dyn DistanceField>, smoothing: f64) -> Self {
+        Self { a, b, smoothing }
+    }
+}
+
+impl DistanceField for SmoothUnion {
+    fn distance(&self, p: &Point3) -> f64 {
+        let (a, b) = (self.a.distance(p), self.b.distance(p));
+        if self.smoothing <= 0. {
+            return a.min(b);
+        }
+        let h = (0.5 + 0.5 * (b - a) / self.smoothing).clamp(0., 1.);
+        b + (a - b) * h - self.smoothing * h * (1. - h)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        // The blend lowers the distance by at most a quarter of the
+        // smoothing, so the surface can bulge out of the shapes by as much
+        let bbox = AABB::from_boxes(&self.a.bounding_box(), &self.b.bounding_box());
+        let padding = self.smoothing.max(0.) / 4.;
+        AABB::new(
+            bbox.x.expand(2. * padding),
+            bbox.y.expand(2. * padding),
+            bbox.z.expand(2. * padding),
+        )
+    }
+}
+
+/// Carves one shape out of another, rounding the cut edges over a distance
+/// of about `smoothing`.
+#[derive(Debug)]
+pub struct SmoothSubtraction {
+    base: Arc<dyn DistanceField>,
+    cut: Arc<dyn DistanceField>,
+    smoothing: f64,
+}
+
+impl SmoothSubtraction {
+    pub fn new(base: Arc<dyn DistanceField>, cut: Arc<dyn DistanceField>, smoothing: f64) -> Self {
+        Self {
+            base,
+            cut,
+            smoothing,
+        }
+    }
+}
+
+impl DistanceField for SmoothSubtraction {
+    fn distance(&self, p: &Point3) -> f64 {
+        let (base, cut) = (self.base.distance(p), -self.cut.distance(p));
+        if self.smoothing <= 0. {
+            return base.max(cut);
+        }
+        let h = (0.5 - 0.5 * (base - cut) / self.smoothing).clamp(0., 1.);
+        base + (cut - base) * h + self.smoothing * h * (1. - h)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.base.bounding_box()
+    }
+}
+
+/// Moves a shape by an offset.
+#[derive(Debug)]
+pub struct Offset {
+    field: Arc<dyn DistanceField>,
+    offset: Vec3,
+}
+
+impl Offset {
+    pub fn new(field: Arc<dyn DistanceField>, offset: Vec3) -> Self {
+        Self { field, offset }
+    }
+}
+
+impl DistanceField for Offset {
+    fn distance(&self, p: &Point3) -> f64 {
+        self.field.distance(&(*p - self.offset))
+    }
+
+    fn bounding_box(&self) -> AABB {
+        &self.field.bounding_box() + self.offset
+    }
+}
+
+/// Repeats a shape on a grid, with `count` copies along each axis spaced
+/// `spacing` apart, starting from the original.
+///
+/// Each point only measures the distance to the nearest copy, so the shape
+/// should fit within a single grid cell around the origin.
+#[derive(Debug)]
+pub struct Repeat {
+    field: Arc<dyn DistanceField>,
+    spacing: Vec3,
+    count: [usize; 3],
+}
+
+impl Repeat {
+    pub fn new(field: Arc<dyn DistanceField>, spacing: Vec3, count: [usize; 3]) -> Self {
+        assert!(count.iter().all(|&n| n > 0), "expected at least one copy");
+        Self {
+            field,
+            spacing,
+            count,
+        }
+    }
+}
+
+impl DistanceField for Repeat {
+    fn distance(&self, p: &Point3) -> f64 {
+        let mut q = *p;
+        for axis in 0..3 {
+            let spacing = self.spacing[axis];
+            if spacing != 0. {
+                let cell = (p[axis] / spacing)
+                    .round()
+                    .clamp(0., (self.count[axis] - 1) as f64);
+                q[axis] -= spacing * cell;
+            }
+        }
+        self.field.distance(&q)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let bbox = self.field.bounding_box();
+        let last = Vec3::new(
+            self.spacing.x * (self.count[0] - 1) as f64,
+            self.spacing.y * (self.count[1] - 1) as f64,
+            self.spacing.z * (self.count[2] - 1) as f64,
+        );
+        AABB::from_boxes(&bbox, &(&bbox + last))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        object::sdf::{SdfBox, SdfSphere},
+        vec3::Vec3,
+        Point3,
+    };
+
+    use super::{DistanceField, Repeat, SmoothSubtraction, SmoothUnion};
+
+    #[test]
+    fn smooth_blends() {
+        let a = Arc::new(SdfSphere::new(Point3::new(-1., 0., 0.), 1.));
+        let b = Arc::new(SdfSphere::new(Point3::new(1., 0., 0.), 1.));
+        let p = Point3::new(0., 1., 0.);
+
+        // Far from the join it's a plain union, and near it the surface fills in
+        let union = SmoothUnion::new(a.clone(), b.clone(), 0.5);
+        assert!((union.distance(&Point3::new(-3., 0., 0.)) - 1.).abs() < 1e-12);
+        assert!(union.distance(&p) < a.distance(&p).min(b.distance(&p)));
+
+        // Side by side boxes bulge out of their own bounds where they join
+        let left = Arc::new(SdfBox::new(Point3::new(-1., 0., 0.), Vec3::new(1., 1., 1.)));
+        let right = Arc::new(SdfBox::new(Point3::new(1., 0., 0.), Vec3::new(1., 1., 1.)));
+        let blend = SmoothUnion::new(left, right, 1.);
+        let bulge = Point3::new(0., 1.1, 0.);
+        assert!(blend.distance(&bulge) < 0.);
+        assert!(blend.bounding_box().y.contains(bulge.y));
+
+        let cut = SmoothSubtraction::new(a.clone(), b, 0.5);
+        assert!((cut.distance(&Point3::new(-3., 0., 0.)) - 1.).abs() < 1e-12);
+        assert!(cut.distance(&Point3::new(0.5, 0., 0.)) > 0.);
+    }
+
+    #[test]
+    fn repeat_on_grid() {
+        let sphere = Arc::new(SdfSphere::new(Point3::zeros(), 0.5));
+        let row = Repeat::new(sphere, Vec3::new(2., 0., 0.), [3, 1, 1]);
+        assert!((row.distance(&Point3::new(4., 0., 0.)) + 0.5).abs() < 1e-12);
+        assert!((row.distance(&Point3::new(7., 0., 0.)) - 2.5).abs() < 1e-12);
+        assert!((row.distance(&Point3::new(2., 3., 0.)) - 2.5).abs() < 1e-12);
+
+        let bbox = row.bounding_box();
+        assert!((bbox.x.min + 0.5).abs() < 1e-12 && (bbox.x.max - 4.5).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{aabb::AABB, vec3::Vec3, Point3};
+
+use super::DistanceField;
+
+#[derive(Debug)]
+pub struct SdfSphere {
+    centre: Point3,
+    radius: f64,
+}
+
+impl SdfSphere {
+    pub fn new(centre: Point3, radius: f64) -> Self {
+        Self { centre, radius }
+    }
+}
+
+impl DistanceField for SdfSphere {
+    fn distance(&self, p: &Point3) -> f64 {
+        (*p - self.centre).length() - self.radius
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let rvec = Vec3::new(self.radius, self.radius, self.radius);
+        AABB::from_points(self.centre - rvec, self.centre + rvec)
+    }
+}
+
+/// An axis-aligned box, given by its centre and the distance from the centre
+/// to each face.
+#[derive(Debug)]
+pub struct SdfBox {
+    centre: Point3,
+    half_size: Vec3,
+}
+
+impl SdfBox {
+    pub fn new(centre: Point3, half_size: Vec3) -> Self {
+        Self { centre, half_size }
+    }
+}
+
+/// The distance to an axis-aligned box centred on the origin.
+fn box_distance(p: Vec3, half_size: Vec3) -> f64 {
+    let q = Vec3::new(p.x.abs(), p.y.abs(), p.z.abs()) - half_size;
+    let outside = Vec3::new(q.x.max(0.), q.y.max(0.), q.z.max(0.)).length();
+    let inside = q.x.max(q.y).max(q.z).min(0.);
+    outside + inside
+}
+
+impl DistanceField for SdfBox {
+    fn distance(&self, p: &Point3) -> f64 {
+        box_distance(*p - self.centre, self.half_size)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        AABB::from_points(self.centre - self.half_size, self.centre + self.half_size)
+    }
+}
+
+/// An axis-aligned box with its edges and corners rounded off to a radius.
+/// The rounding is inside the given size.
+#[derive(Debug)]
+pub struct RoundedBox {
+    centre: Point3,
+    half_size: Vec3,
+    radius: f64,
+}
+
+impl RoundedBox {
+    pub fn new(centre: Point3, half_size: Vec3, radius: f64) -> Self {
+        assert!(
+            radius <= half_size.x.min(half_size.y).min(half_size.z),
+            "rounding radius must fit inside the box"
+        );
+        Self {
+            centre,
+            half_size,
+            radius,
+        }
+    }
+}
+
+impl DistanceField for RoundedBox {
+    fn distance(&self, p: &Point3) -> f64 {
+        let inner = self.half_size - Vec3::new(self.radius, self.radius, self.radius);
+        box_distance(*p - self.centre, inner) - self.radius
+    }
+
+    fn bounding_box(&self) -> AABB {
+        AABB::from_points(self.centre - self.half_size, self.centre + self.half_size)
+    }
+}
+
+/// A torus lying in the xz plane.
+#[derive(Debug)]
+pub struct SdfTorus {
+    centre: Point3,
+    major_radius: f64,
+    minor_radius: f64,
+}
+
+impl SdfTorus {
+    pub fn new(centre: Point3, major_radius: f64, minor_radius: f64) -> Self {
+        Self {
+            centre,
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+impl DistanceField for SdfTorus {
+    fn distance(&self, p: &Point3) -> f64 {
+        let p = *p - self.centre;
+        let ring = p.x.hypot(p.z) - self.major_radius;
+        ring.hypot(p.y) - self.minor_radius
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let outer = self.major_radius + self.minor_radius;
+        let extent = Vec3::new(outer, self.minor_radius, outer);
+        AABB::from_points(self.centre - extent, self.centre + extent)
+    }
+}
+
+/// The Mandelbulb fractal, centred on the origin, with a distance estimated
+/// from the escape of its iterated function.
+#[derive(Debug)]
+pub struct Mandelbulb {
+    power: f64,
+    iterations: usize,
+    /// The radius of a sphere containing the whole fractal.
+    radius: f64,
+}
+
+impl Mandelbulb {
+    /// Creates the fractal for the given power, which is 8 for the classic
+    /// shape. More iterations give more detail.
+    pub fn new(power: f64, iterations: usize) -> Self {
+        // Points further out than this escape, as for the Multibrot set of
+        // the same power, and the escape radius of 2 bounds lower powers
+        let radius = if power > 1. {
+            2f64.powf(1. / (power - 1.)).min(2.)
+        } else {
+            2.
+        };
+        Self {
+            power,
+            iterations,
+            radius,
+        }
+    }
+}
+
+impl Default for Mandelbulb {
+    fn default() -> Self {
+        Self::new(8., 12)
+    }
+}
+
+impl DistanceField for Mandelbulb {
+    fn distance(&self, p: &Point3) -> f64 {
+        let mut z = *p;
+        let mut dr = 1.;
+        let mut r = z.length();
+        for _ in 0..self.iterations {
+            if r > 2. {
+                break;
+            }
+
+            // Raise z to the power in spherical coordinates, tracking the
+            // derivative for the distance estimate
+            dr = r.powf(self.power - 1.) * self.power * dr + 1.;
+            if r > 0. {
+                let theta = (z.z / r).acos() * self.power;
+                let phi = z.y.atan2(z.x) * self.power;
+                z = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                ) * r.powf(self.power);
+            }
+            z += *p;
+            r = z.length();
+        }
+        if r == 0. {
+            return 0.;
+        }
+        0.5 * r.ln() * r / dr
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let extent = Vec3::new(self.radius, self.radius, self.radius);
+        AABB::from_points(-extent, extent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{
+        interval, material,
+        object::{Hittable, Sdf},
+        ray::Ray,
+        vec3::Vec3,
+        Colour, Point3,
+    };
+
+    use super::{DistanceField, Mandelbulb, RoundedBox, SdfBox, SdfTorus};
+
+    #[test]
+    fn distances() {
+        let cube = SdfBox::new(Point3::zeros(), Vec3::new(1., 1., 1.));
+        assert!((cube.distance(&Point3::new(3., 0., 0.)) - 2.).abs() < 1e-12);
+        assert!((cube.distance(&Point3::new(2., 2., 1.)) - 2f64.sqrt()).abs() < 1e-12);
+        assert!((cube.distance(&Point3::new(0.5, 0., 0.)) + 0.5).abs() < 1e-12);
+
+        // Rounding only changes the distance near the corners
+        let rounded = RoundedBox::new(Point3::zeros(), Vec3::new(1., 1., 1.), 0.25);
+        assert!((rounded.distance(&Point3::new(3., 0., 0.)) - 2.).abs() < 1e-12);
+        assert!(rounded.distance(&Point3::new(1., 1., 1.)) > 0.);
+
+        let torus = SdfTorus::new(Point3::zeros(), 2., 0.5);
+        assert!((torus.distance(&Point3::new(2., 1., 0.)) - 0.5).abs() < 1e-12);
+        assert!((torus.distance(&Point3::zeros()) - 1.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn mandelbulb_inside_its_bounds() {
+        let bulb = Mandelbulb::default();
+        assert!(bulb.distance(&Point3::zeros()) <= 0.);
+        assert!(bulb.distance(&Point3::new(1.3, 0., 0.)) > 0.);
+        assert!(bulb.distance(&Point3::new(0., 0., 3.)) > 0.);
+
+        // Lower powers spread further out, and the quadratic fractal reaches
+        // all the way to the escape radius
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        let quadratic = Sdf::new(Arc::new(Mandelbulb::new(2., 20)), mat);
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0., 0., -3.), Vec3::new(0., 0., 1.), 0.);
+        let hit = quadratic.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.point.z + 2.).abs() < 1e-3);
+        assert!(hit.front_face);
+    }
+}
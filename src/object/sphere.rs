@@ -2,7 +2,7 @@ use std::{f64::consts, sync::Arc};
 
 use rand::rngs;
 
-use crate::{aabb::AABB, interval, material, ray::Ray, vec3::Vec3, Point3};
+use crate::{aabb::AABB, interval, material, ray::Ray, stats, vec3::Vec3, Point3};
 
 use super::object;
 
@@ -50,6 +50,8 @@ impl object::Hittable for Sphere {
         ray_t: &interval::Interval,
         _rng: &mut rngs::SmallRng,
     ) -> Option<object::HitRecord> {
+        stats::record_hit("Sphere");
+
         let oc = r.origin - self.centre;
         let a = r.direction.length_squared();
         let half_b = oc.dot(r.direction);
@@ -0,0 +1,239 @@
+use std::{f64::consts, sync::Arc};
+
+use rand::rngs;
+
+use crate::{aabb::AABB, interval::Interval, material, ray::Ray, stats, vec3::Vec3, Point3};
+
+use super::{
+    frame::{self, Frame},
+    HitRecord, Hittable,
+};
+
+/// A ring-shaped torus around an axis.
+///
+/// The hit record's u is the angle around the axis and v the angle around
+/// the tube.
+#[derive(Debug)]
+pub struct Torus {
+    frame: Frame,
+    major_radius: f64,
+    minor_radius: f64,
+    mat: Arc<dyn material::Material>,
+    aabb: AABB,
+}
+
+impl Torus {
+    /// Creates a torus whose tube of radius `minor_radius` circles the axis
+    /// through `centre` at a distance of `major_radius`.
+    pub fn new(
+        centre: Point3,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        mat: Arc<dyn material::Material>,
+    ) -> Self {
+        assert!(
+            0. < minor_radius && minor_radius <= major_radius,
+            "the tube of a torus must fit inside its ring"
+        );
+        let frame = Frame::new(centre, axis);
+        let ring = frame.disk_box(0., major_radius);
+        let tube = Vec3::new(minor_radius, minor_radius, minor_radius);
+        let aabb = AABB::from_points(
+            Point3::new(ring.x.min, ring.y.min, ring.z.min) - tube,
+            Point3::new(ring.x.max, ring.y.max, ring.z.max) + tube,
+        );
+        Self {
+            frame,
+            major_radius,
+            minor_radius,
+            mat,
+            aabb,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("Torus");
+
+        let (o, d) = self.frame.local_ray(r);
+        let (major, minor) = (self.major_radius, self.minor_radius);
+
+        // Clip the ray to the bounding sphere, and solve from where it enters
+        // to keep the quartic's coefficients small
+        let bound = major + minor;
+        let (enter, exit) = frame::quadratic_roots(1., o.dot(d), o.dot(o) - bound * bound)?;
+        let start = enter.max(ray_t.min);
+        let end = exit.min(ray_t.max);
+        if start > end {
+            return None;
+        }
+        let o = o + d * start;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2), with p = o + t d
+        let m = o.dot(d);
+        let n = o.dot(o) + major * major - minor * minor;
+        let four_r2 = 4. * major * major;
+        let coeffs = [
+            n * n - four_r2 * (o.x * o.x + o.z * o.z),
+            4. * m * n - four_r2 * 2. * (o.x * d.x + o.z * d.z),
+            4. * m * m + 2. * n - four_r2 * (d.x * d.x + d.z * d.z),
+            4. * m,
+            1.,
+        ];
+        let t = *polynomial_roots(&coeffs, 0., end - start)
+            .as_slice()
+            .iter()
+            .find(|&&t| ray_t.contains(t + start))?;
+
+        let p = o + d * t;
+        let ring_point = Vec3::new(p.x, 0., p.z).unit() * major;
+        let normal = p - ring_point;
+        let u = (p.z.atan2(p.x) + consts::PI) / consts::TAU;
+        let outward = Vec3::new(p.x, 0., p.z).unit();
+        let v = (normal.y.atan2(normal.dot(outward)) + consts::PI) / consts::TAU;
+        Some(
+            self.frame
+                .hit_record(r, t + start, normal, (u, v), &self.mat),
+        )
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+fn evaluate(coeffs: &[f64], t: f64) -> f64 {
+    coeffs.iter().rev().fold(0., |acc, c| acc * t + c)
+}
+
+/// Up to four real roots of a polynomial, stored inline so that finding
+/// them doesn't allocate.
+struct Roots {
+    values: [f64; 4],
+    len: usize,
+}
+
+impl Roots {
+    fn new() -> Self {
+        Self {
+            values: [0.; 4],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, t: f64) {
+        self.values[self.len] = t;
+        self.len += 1;
+    }
+
+    fn as_slice(&self) -> &[f64] {
+        &self.values[..self.len]
+    }
+}
+
+/// Finds the real roots of a polynomial of degree at most four within
+/// `[lo, hi]` in increasing order, given its coefficients from the constant
+/// term up. Between the roots of the derivative the polynomial is monotonic,
+/// so each of those intervals holds at most one root, found by bisection.
+fn polynomial_roots(coeffs: &[f64], lo: f64, hi: f64) -> Roots {
+    let degree = coeffs.len() - 1;
+    let mut roots = Roots::new();
+    if degree == 1 {
+        let t = -coeffs[0] / coeffs[1];
+        if (lo..=hi).contains(&t) {
+            roots.push(t);
+        }
+        return roots;
+    }
+
+    let mut derivative = [0.; 4];
+    for i in 1..=degree {
+        derivative[i - 1] = coeffs[i] * i as f64;
+    }
+    let turning = polynomial_roots(&derivative[..degree], lo, hi);
+    let mut bounds = [0.; 5];
+    bounds[0] = lo;
+    bounds[1..=turning.len].copy_from_slice(turning.as_slice());
+    bounds[turning.len + 1] = hi;
+
+    for pair in bounds[..turning.len + 2].windows(2) {
+        let (mut a, mut b) = (pair[0], pair[1]);
+        let (fa, fb) = (evaluate(coeffs, a), evaluate(coeffs, b));
+        if fa == 0. {
+            roots.push(a);
+            continue;
+        }
+        if fa.signum() == fb.signum() {
+            continue;
+        }
+        for _ in 0..64 {
+            let mid = 0.5 * (a + b);
+            if (evaluate(coeffs, mid) < 0.) == (fa < 0.) {
+                a = mid;
+            } else {
+                b = mid;
+            }
+        }
+        roots.push(0.5 * (a + b));
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{interval, material, object::Hittable, ray::Ray, vec3::Vec3, Colour, Point3};
+
+    use super::{polynomial_roots, Torus};
+
+    #[test]
+    fn roots_of_quartic() {
+        // (t - 1)(t - 2)(t - 3)(t - 4)
+        let roots = polynomial_roots(&[24., -50., 35., -10., 1.], 0., 10.);
+        assert_eq!(roots.len, 4);
+        for (root, expected) in roots.as_slice().iter().zip([1., 2., 3., 4.]) {
+            assert!((root - expected).abs() < 1e-9);
+        }
+        assert_eq!(
+            polynomial_roots(&[24., -50., 35., -10., 1.], 1.5, 2.5).len,
+            1
+        );
+    }
+
+    #[test]
+    fn hit_through_the_ring() {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        // Lying flat, so seen from above it's a ring
+        let torus = Torus::new(Point3::zeros(), Vec3::new(0., 1., 0.), 2., 0.5, mat);
+        let bbox = torus.bounding_box();
+        assert!((bbox.x.max - 2.5).abs() < 1e-9 && (bbox.y.max - 0.5).abs() < 1e-9);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(10., 0., 0.), Vec3::new(-1., 0., 0.), 0.);
+        let hit = torus.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 7.5).abs() < 1e-9);
+        assert!(hit.normal.is_close(&Vec3::new(1., 0., 0.)));
+
+        // From inside the tube, the far side of it
+        let inside = interval::Interval::new(7.6, f64::INFINITY);
+        let hit = torus.hit(&r, &inside, &mut rng).unwrap();
+        assert!((hit.t - 8.5).abs() < 1e-9);
+        assert!(!hit.front_face);
+
+        // Down through the hole in the middle
+        let r = Ray::new(Point3::new(0., 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        assert!(torus.hit(&r, &interval::UNIVERSE, &mut rng).is_none());
+
+        let r = Ray::new(Point3::new(2., 5., 0.), Vec3::new(0., -1., 0.), 0.);
+        let hit = torus.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 4.5).abs() < 1e-9);
+        assert!(hit.normal.is_close(&Vec3::new(0., 1., 0.)));
+    }
+}
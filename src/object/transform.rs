@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use rand::rngs;
+
+use crate::{
+    aabb::AABB,
+    interval::{Interval, UNIVERSE},
+    matrix::Matrix4,
+    ray::Ray,
+    stats,
+};
+
+use super::{HitRecord, Hittable};
+
+/// An object placed in the world by an affine transform, which can combine
+/// rotation, non-uniform scaling, shear and translation.
+///
+/// The object is shared rather than copied, so a heavy mesh can be instanced
+/// many times with a `Transform` for each copy while only being stored once.
+#[derive(Debug)]
+pub struct Transform {
+    object: Arc<dyn Hittable>,
+    matrix: Matrix4,
+    inverse: Matrix4,
+    bbox: AABB,
+}
+
+impl Transform {
+    /// Places an object by transforming it from its own space into world
+    /// space. The matrix must be invertible.
+    pub fn new(object: Arc<dyn Hittable>, matrix: Matrix4) -> Self {
+        let inverse = matrix
+            .inverse()
+            .expect("transform matrix must be invertible");
+        let bbox = transform_box(&matrix, object.bounding_box());
+        Self {
+            object,
+            matrix,
+            inverse,
+            bbox,
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+}
+
+/// Finds the box around a transformed box, by adding up the smallest and
+/// largest contributions of each axis of the original box (Arvo's method).
+fn transform_box(m: &Matrix4, bbox: &AABB) -> AABB {
+    let mut axes = [UNIVERSE; 3];
+    for (i, axis) in axes.iter_mut().enumerate() {
+        let mut min = m.rows[i][3];
+        let mut max = m.rows[i][3];
+        for j in 0..3 {
+            let factor = m.rows[i][j];
+            // Skip unused axes, which may be unbounded
+            if factor == 0. {
+                continue;
+            }
+            let a = factor * bbox.axis(j).min;
+            let b = factor * bbox.axis(j).max;
+            min += a.min(b);
+            max += a.max(b);
+        }
+        *axis = Interval::new(min, max);
+    }
+    let [x, y, z] = axes;
+    AABB::new(x, y, z).pad()
+}
+
+impl Hittable for Transform {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("Transform");
+
+        // Rays have unit directions, so distances along the ray in object
+        // space are scaled by the length of the transformed direction
+        let direction = self.inverse.transform_vector(r.direction);
+        let scale = direction.length();
+        let local = Ray::new(self.inverse.transform_point(r.origin), direction, r.time);
+        let local_t = Interval::new(ray_t.min * scale, ray_t.max * scale);
+
+        let mut hitrec = self.object.hit(&local, &local_t, rng)?;
+
+        // The normal was already flipped to face the ray in object space, and
+        // transforming both keeps it that way
+        hitrec.t /= scale;
+        hitrec.point = self.matrix.transform_point(hitrec.point);
+        hitrec.normal = Matrix4::transform_normal(&self.inverse, hitrec.normal).unit();
+        Some(hitrec)
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{
+        interval, material,
+        matrix::Matrix4,
+        object::{Hittable, Sphere},
+        ray::Ray,
+        vec3::Vec3,
+        Colour, Point3,
+    };
+
+    use super::Transform;
+
+    fn unit_sphere() -> Arc<Sphere> {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        Arc::new(Sphere::new(Point3::zeros(), 1., mat))
+    }
+
+    #[test]
+    fn scaled_and_moved_sphere() {
+        // An ellipsoid twice as wide as it is tall, centred at (0, 0, -5)
+        let m =
+            Matrix4::translation(Vec3::new(0., 0., -5.)) * Matrix4::scaling(Vec3::new(2., 1., 1.));
+        let ellipsoid = Transform::new(unit_sphere(), m);
+
+        let bbox = ellipsoid.bounding_box();
+        assert!((bbox.x.min + 2.).abs() < 1e-3 && (bbox.x.max - 2.).abs() < 1e-3);
+        assert!((bbox.z.min + 6.).abs() < 1e-3 && (bbox.z.max + 4.).abs() < 1e-3);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(5., 0., -5.), Vec3::new(-1., 0., 0.), 0.);
+        let hit = ellipsoid.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!((hit.t - 3.).abs() < 1e-9);
+        assert!(hit.point.is_close(&Point3::new(2., 0., -5.)));
+        assert!(hit.normal.is_close(&Vec3::new(1., 0., 0.)));
+        assert!(hit.front_face);
+
+        // The normal of a stretched sphere leans towards the flatter axis
+        let p = Point3::new(2f64.sqrt(), 1. / 2f64.sqrt(), -5.);
+        let r = Ray::new(
+            p * 2. - Point3::new(0., 0., -5.),
+            Vec3::new(-2., -1., 0.),
+            0.,
+        );
+        let hit = ellipsoid.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+        assert!(hit.point.is_close(&p));
+        assert!(hit.normal.is_close(&Vec3::new(1., 2., 0.).unit()));
+
+        // Distances are in world space, so the interval still limits the hit
+        let short = interval::Interval::new(0.001, 2.9);
+        let r = Ray::new(Point3::new(5., 0., -5.), Vec3::new(-1., 0., 0.), 0.);
+        assert!(ellipsoid.hit(&r, &short, &mut rng).is_none());
+    }
+
+    #[test]
+    fn instances_share_the_object() {
+        let sphere = unit_sphere();
+        let instances: Vec<_> = (0..10)
+            .map(|i| {
+                let offset = Vec3::new(3. * i as f64, 0., 0.);
+                Transform::new(sphere.clone(), Matrix4::translation(offset))
+            })
+            .collect();
+        assert_eq!(Arc::strong_count(&sphere), 11);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(27., 0., 5.), Vec3::new(0., 0., -1.), 0.);
+        assert!(instances[9]
+            .hit(&r, &interval::UNIVERSE, &mut rng)
+            .is_some());
+        assert!(instances[8]
+            .hit(&r, &interval::UNIVERSE, &mut rng)
+            .is_none());
+    }
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use rand::rngs;
 
-use crate::{aabb::AABB, interval::Interval, ray::Ray, vec3::Vec3};
+use crate::{aabb::AABB, interval::Interval, ray::Ray, stats, vec3::Vec3};
 
 use super::Hittable;
 
@@ -31,6 +31,8 @@ impl<'a> Hittable for Translate {
         ray_t: &Interval,
         rng: &mut rngs::SmallRng,
     ) -> Option<super::HitRecord> {
+        stats::record_hit("Translate");
+
         let offset_r = Ray::new(r.origin - self.offset, r.direction, r.time);
 
         let mut hitrec = self.object.hit(&offset_r, ray_t, rng)?;
@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use rand::rngs;
+
+use crate::{aabb::AABB, interval::Interval, material, ray::Ray, stats, vec3::Vec3, Point3};
+
+use super::{HitRecord, Hittable};
+
+/// A single triangle. Without per-vertex normals the triangle is flat
+/// shaded, and without per-vertex texture coordinates the hit record's u and
+/// v are the barycentric coordinates of the hit.
+#[derive(Debug)]
+pub struct Triangle {
+    vertices: [Point3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    mat: Arc<dyn material::Material>,
+    aabb: AABB,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3, mat: Arc<dyn material::Material>) -> Self {
+        let aabb = AABB::from_boxes(&AABB::from_points(a, b), &AABB::from_points(a, c)).pad();
+        Self {
+            vertices: [a, b, c],
+            normals: None,
+            uvs: None,
+            mat,
+            aabb,
+        }
+    }
+
+    /// Interpolates the given vertex normals across the triangle for smooth
+    /// shading.
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.normals = Some(normals);
+        self
+    }
+
+    /// Interpolates the given texture coordinates across the triangle.
+    pub fn with_uvs(mut self, uvs: [(f64, f64); 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, ray_t: &Interval, _rng: &mut rngs::SmallRng) -> Option<HitRecord> {
+        stats::record_hit("Triangle");
+
+        let [a, b, c] = self.vertices;
+        let (t, b1, b2) = intersect(r, ray_t, a, b, c)?;
+        Some(surface_hit(
+            r,
+            t,
+            (b1, b2),
+            (b - a).cross(c - a),
+            self.normals,
+            self.uvs,
+            &self.mat,
+        ))
+    }
+
+    fn bounding_box(&self) -> &AABB {
+        &self.aabb
+    }
+}
+
+/// Intersects a ray with a triangle using the Möller–Trumbore algorithm,
+/// returning the distance along the ray and the barycentric coordinates of
+/// the hit relative to the second and third vertices.
+pub(crate) fn intersect(
+    r: &Ray,
+    ray_t: &Interval,
+    a: Point3,
+    b: Point3,
+    c: Point3,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = r.direction.cross(edge2);
+    let det = edge1.dot(p);
+
+    // The ray is parallel to the triangle
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1. / det;
+
+    let s = r.origin - a;
+    let b1 = s.dot(p) * inv_det;
+    if !(0. ..=1.).contains(&b1) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let b2 = r.direction.dot(q) * inv_det;
+    if b2 < 0. || b1 + b2 > 1. {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inv_det;
+    if !ray_t.contains(t) {
+        return None;
+    }
+    Some((t, b1, b2))
+}
+
+/// Builds the hit record for a point on a triangle from its barycentric
+/// coordinates, interpolating any per-vertex normals and texture coordinates.
+pub(crate) fn surface_hit<'a>(
+    r: &Ray,
+    t: f64,
+    (b1, b2): (f64, f64),
+    geometric_normal: Vec3,
+    normals: Option<[Vec3; 3]>,
+    uvs: Option<[(f64, f64); 3]>,
+    mat: &'a Arc<dyn material::Material>,
+) -> HitRecord<'a> {
+    let b0 = 1. - b1 - b2;
+    let (u, v) = match uvs {
+        Some([uv0, uv1, uv2]) => (
+            b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+            b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1,
+        ),
+        None => (b1, b2),
+    };
+
+    let geometric_normal = geometric_normal.unit();
+    let mut hitrec = HitRecord::new(r.at(t), geometric_normal, t, u, v, mat);
+    hitrec.set_face_normal(r, geometric_normal);
+
+    // Shade with the interpolated normal, but keep it on the side of the
+    // surface that the ray hit
+    if let Some([n0, n1, n2]) = normals {
+        let shading_normal = (n0 * b0 + n1 * b1 + n2 * b2).unit();
+        if !shading_normal.near_zero() {
+            hitrec.normal = if hitrec.front_face {
+                shading_normal
+            } else {
+                -shading_normal
+            };
+        }
+    }
+    hitrec
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{interval, material, object::Hittable, ray::Ray, vec3::Vec3, Colour, Point3};
+
+    use super::Triangle;
+
+    fn triangle() -> Triangle {
+        let mat = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        Triangle::new(
+            Point3::new(0., 0., -1.),
+            Point3::new(1., 0., -1.),
+            Point3::new(0., 1., -1.),
+            mat,
+        )
+    }
+
+    #[test]
+    fn barycentric_coordinates() {
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let triangle = triangle();
+        let r = Ray::new(Point3::new(0.25, 0.5, 0.), Vec3::new(0., 0., -1.), 0.);
+        let hit = triangle.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+
+        assert!((hit.t - 1.).abs() < 1e-12);
+        assert!((hit.u - 0.25).abs() < 1e-12);
+        assert!((hit.v - 0.5).abs() < 1e-12);
+        assert!(hit.front_face);
+        assert!(hit.normal.is_close(&Vec3::new(0., 0., 1.)));
+
+        let r = Ray::new(Point3::new(0.75, 0.5, 0.), Vec3::new(0., 0., -1.), 0.);
+        assert!(triangle.hit(&r, &interval::UNIVERSE, &mut rng).is_none());
+    }
+
+    #[test]
+    fn interpolate_attributes() {
+        let tilted = Vec3::new(1., 0., 1.).unit();
+        let triangle = triangle().with_normals([tilted, tilted, tilted]).with_uvs([
+            (0., 0.),
+            (0., 1.),
+            (1., 1.),
+        ]);
+
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(Point3::new(0.25, 0.5, -2.), Vec3::new(0., 0., 1.), 0.);
+        let hit = triangle.hit(&r, &interval::UNIVERSE, &mut rng).unwrap();
+
+        // Hit from behind, so the shading normal is flipped too
+        assert!(!hit.front_face);
+        assert!(hit.normal.is_close(&-tilted));
+        assert!((hit.u - 0.5).abs() < 1e-12);
+        assert!((hit.v - 0.75).abs() < 1e-12);
+    }
+}
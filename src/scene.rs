@@ -1,97 +1,319 @@
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::{seq::SliceRandom, thread_rng, SeedableRng};
+use rand::SeedableRng;
 use rayon::prelude::*;
-use std::{io, sync::atomic::AtomicUsize};
+use std::{
+    collections::BTreeMap,
+    io,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 
 use crate::{
     camera::Camera,
+    image::ImageSink,
     interval,
-    material::Behaviour,
-    object::{self, Hittable},
+    light_group::{LightGroupLayers, LightSource},
+    material::{self, Behaviour, MediumStack},
+    object::{self, HitRecord, Hittable},
     ray::Ray,
-    Colour,
+    stats::{self, PathEnd, RenderStats},
+    vec3::Vec3,
+    Colour, Point3,
 };
 use rand::{rngs, Rng};
 
+/// A rectangular region of the image, in pixels, with the origin at the top
+/// left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Splits an image into tiles of at most `size` by `size` pixels, in
+    /// row-major order.
+    pub fn split(image_width: usize, image_height: usize, size: usize) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        for y in (0..image_height).step_by(size) {
+            for x in (0..image_width).step_by(size) {
+                tiles.push(Tile::new(
+                    x,
+                    y,
+                    size.min(image_width - x),
+                    size.min(image_height - y),
+                ));
+            }
+        }
+        tiles
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+}
+
+/// Converts a linear colour into gamma corrected 8 bit RGB values.
+pub fn to_rgb8(colour: Colour) -> (u8, u8, u8) {
+    let r = (colour.x.sqrt() * 255.999) as u8;
+    let g = (colour.y.sqrt() * 255.999) as u8;
+    let b = (colour.z.sqrt() * 255.999) as u8;
+
+    (r, g, b)
+}
+
+/// Whether a point projected into the image can be seen in the render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Visible,
+    /// In view of the camera, but hidden behind another object.
+    Occluded,
+    /// In front of the camera, but outside of the image.
+    OffScreen,
+    BehindCamera,
+}
+
+/// A point in the scene projected into the image, in pixels from the top
+/// left corner, where each pixel covers a unit square.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenPoint {
+    pub x: f64,
+    pub y: f64,
+    /// Distance of the point in front of the camera.
+    pub depth: f64,
+    pub visibility: Visibility,
+}
+
+/// The object seen through a pixel.
+#[derive(Debug)]
+pub struct Pick<'a> {
+    /// The object that was hit, found inside any lists or BVHs holding it.
+    /// Compare it with the objects added to the world using `Arc::ptr_eq`.
+    pub object: &'a Arc<dyn Hittable>,
+    pub hit: HitRecord<'a>,
+}
+
 pub struct Scene {
     world: object::HittableList,
-    camera: Camera,
+    camera: Box<dyn Camera>,
     max_depth: usize,
     samples_per_pixel: usize,
     image_width: usize,
     image_height: usize,
     background: Colour,
+    bucket_rows: usize,
+    collect_stats: bool,
+    stats: Mutex<Option<RenderStats>>,
 }
 
 impl Scene {
     pub fn new(
         world: object::HittableList,
-        camera: Camera,
+        camera: impl Camera + 'static,
         max_depth: usize,
         samples_per_pixel: usize,
         image_width: usize,
         image_height: usize,
         background: Colour,
     ) -> Self {
+        let mut camera = camera;
+        let mut rng = rngs::SmallRng::from_rng(rand::thread_rng()).unwrap();
+        camera.focus(&world, &mut rng);
+
         Self {
             world,
-            camera,
+            camera: Box::new(camera),
             max_depth,
             samples_per_pixel,
             image_width,
             image_height,
             background,
+            bucket_rows: 16,
+            collect_stats: false,
+            stats: Mutex::new(None),
         }
     }
 
-    pub fn render(&self, pixel_buffer: &mut Vec<u8>) -> io::Result<()> {
-        let pb = ProgressBar::new(self.image_height as u64);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {percent}% ({eta_precise})"));
+    /// Enables collecting performance counters during `render`. This adds a
+    /// small overhead to every ray, so it is disabled by default.
+    pub fn collect_stats(&mut self, collect_stats: bool) -> &mut Self {
+        self.collect_stats = collect_stats;
+        self
+    }
 
-        let mut rows: Vec<usize> = (0..self.image_height).collect();
-        let mut cols: Vec<usize> = (0..self.image_width).collect();
-        rows.shuffle(&mut thread_rng());
-        cols.shuffle(&mut thread_rng());
+    /// Gets the statistics from the most recent render, if they were
+    /// collected.
+    pub fn stats(&self) -> Option<RenderStats> {
+        self.stats.lock().unwrap().clone()
+    }
 
-        let count = AtomicUsize::new(0);
+    pub fn image_width(&self) -> usize {
+        self.image_width
+    }
 
-        let mut pixelbuff: Vec<(usize, Vec<u8>)> = rows
-            .par_iter()
-            .map(|row| {
-                let mut row_buffer = vec![0; self.image_width * 3];
+    pub fn image_height(&self) -> usize {
+        self.image_height
+    }
 
+    /// Renders a single tile of the image, returning the linear (not gamma
+    /// corrected) colour of each pixel in row-major order.
+    pub fn render_tile(&self, tile: &Tile) -> Vec<Colour> {
+        (tile.y..tile.y + tile.height)
+            .into_par_iter()
+            .flat_map_iter(|row| {
                 let mut rng = rngs::SmallRng::from_rng(rand::thread_rng()).unwrap();
+                (tile.x..tile.x + tile.width)
+                    .map(|col| self.sample_pixel(row, col, &mut rng))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
 
-                for col in &cols {
-                    let pixel_colour = self.render_pixel(*row, *col, &mut rng);
-
-                    let pixel_offset = col * 3;
+    /// Sets the number of rows rendered together by `render_to`, which bounds
+    /// the memory used for pixels that have not been written out yet.
+    pub fn bucket_rows(&mut self, bucket_rows: usize) -> &mut Self {
+        self.bucket_rows = bucket_rows.max(1);
+        self
+    }
 
-                    row_buffer[pixel_offset] = pixel_colour.0;
-                    row_buffer[pixel_offset + 1] = pixel_colour.1;
-                    row_buffer[pixel_offset + 2] = pixel_colour.2;
-                }
-                let i = count.fetch_add(1, std::sync::atomic::Ordering::AcqRel);
+    /// Renders the scene into a buffer of gamma corrected 8 bit RGB values,
+    /// in row-major order. The buffer must hold exactly one image.
+    pub fn render(&self, pixel_buffer: &mut [u8]) -> io::Result<()> {
+        let expected = self.image_width * self.image_height * 3;
+        if pixel_buffer.len() != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "pixel buffer holds {} bytes, but a {}x{} image needs {}",
+                    pixel_buffer.len(),
+                    self.image_width,
+                    self.image_height,
+                    expected
+                ),
+            ));
+        }
+        let pb = self.progress_bar();
+        let start = Instant::now();
+        let _stats_guard = self.collect_stats.then(stats::enable);
+        let render_stats = Mutex::new(RenderStats::new());
 
-                println!("count {}", i);
+        self.render_rows(0, pixel_buffer, &pb, &render_stats);
 
-                (*row, row_buffer)
-            })
-            .collect();
+        pb.finish();
+        self.store_stats(render_stats, start);
+        Ok(())
+    }
 
-        pixelbuff.sort_unstable_by_key(|elem| elem.0);
+    /// Renders the scene in buckets of rows, streaming each finished bucket to
+    /// the sink from top to bottom. Only a single bucket of pixels is held in
+    /// memory at a time.
+    pub fn render_to(&self, sink: &mut dyn ImageSink) -> io::Result<()> {
+        let pb = self.progress_bar();
+        let start = Instant::now();
+        let _stats_guard = self.collect_stats.then(stats::enable);
+        let render_stats = Mutex::new(RenderStats::new());
 
-        let pixelbuff: Vec<u8> = pixelbuff.into_iter().flat_map(|el| el.1).collect();
+        let row_length = self.image_width * 3;
+        let mut bucket = vec![0; self.bucket_rows * row_length];
+        for first_row in (0..self.image_height).step_by(self.bucket_rows) {
+            let rows = self.bucket_rows.min(self.image_height - first_row);
+            let bucket = &mut bucket[..rows * row_length];
 
-        // TODO: don't use new vec
-        pixel_buffer.copy_from_slice(&pixelbuff);
+            self.render_rows(first_row, bucket, &pb, &render_stats);
+            sink.write_rows(bucket)?;
+        }
+        sink.finish()?;
 
+        pb.finish();
+        self.store_stats(render_stats, start);
         Ok(())
     }
 
+    fn progress_bar(&self) -> ProgressBar {
+        let pb = ProgressBar::new(self.image_height as u64);
+        pb.set_style(ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {percent}% ({eta_precise})"));
+        pb
+    }
+
+    /// Renders consecutive rows starting at `first_row` in parallel into a
+    /// buffer that holds a whole number of rows.
+    fn render_rows(
+        &self,
+        first_row: usize,
+        buffer: &mut [u8],
+        pb: &ProgressBar,
+        render_stats: &Mutex<RenderStats>,
+    ) {
+        buffer
+            .par_chunks_mut(self.image_width * 3)
+            .enumerate()
+            .for_each(|(i, row_buffer)| {
+                let row = first_row + i;
+                let mut rng = rngs::SmallRng::from_rng(rand::thread_rng()).unwrap();
+
+                // Discard anything left on this thread from other renders
+                if self.collect_stats {
+                    stats::take_local();
+                }
+
+                for (col, pixel) in row_buffer.chunks_exact_mut(3).enumerate() {
+                    let pixel_colour = self.render_pixel(row, col, &mut rng);
+
+                    pixel[0] = pixel_colour.0;
+                    pixel[1] = pixel_colour.1;
+                    pixel[2] = pixel_colour.2;
+                }
+                if self.collect_stats {
+                    render_stats.lock().unwrap().merge(&stats::take_local());
+                }
+
+                pb.inc(1);
+            });
+    }
+
+    fn store_stats(&self, render_stats: Mutex<RenderStats>, start: Instant) {
+        if self.collect_stats {
+            let mut render_stats = render_stats.into_inner().unwrap();
+            render_stats.elapsed = start.elapsed();
+            *self.stats.lock().unwrap() = Some(render_stats);
+        }
+    }
+
     fn render_pixel(&self, row: usize, col: usize, rng: &mut rngs::SmallRng) -> (u8, u8, u8) {
+        to_rgb8(self.sample_pixel(row, col, rng))
+    }
+
+    /// Averages all of the samples for a pixel into a linear colour, scaled by
+    /// the camera's exposure.
+    fn sample_pixel(&self, row: usize, col: usize, rng: &mut rngs::SmallRng) -> Colour {
         let mut pixel_colour = Colour::zeros();
+        self.for_each_sample(row, col, rng, |r, weight, rng| {
+            pixel_colour += self.ray_colour(r, self.max_depth, rng) * weight;
+        });
+        pixel_colour * (self.camera.exposure() / self.samples_per_pixel as f64)
+    }
+
+    /// Generates the stratified camera rays for each sample of a pixel, along
+    /// with their weights. Samples blocked inside the camera are skipped.
+    fn for_each_sample(
+        &self,
+        row: usize,
+        col: usize,
+        rng: &mut rngs::SmallRng,
+        mut f: impl FnMut(&Ray, f64, &mut rngs::SmallRng),
+    ) {
         let sqrt_spp = (self.samples_per_pixel as f64).sqrt().round() as usize;
 
         for i in 0..sqrt_spp {
@@ -100,45 +322,417 @@ impl Scene {
                     / (self.image_width - 1) as f64;
                 let v = (row as f64 + (j as f64 + rng.gen::<f64>()) / sqrt_spp as f64)
                     / (self.image_height - 1) as f64;
-                let r = self.camera.get_ray(u, v, rng);
-                pixel_colour += self.ray_colour(&r, self.max_depth, rng);
+                if let Some((r, weight)) = self.camera.sample_ray(u, v, rng) {
+                    f(&r, weight, rng);
+                }
+            }
+        }
+    }
+
+    /// Renders the scene with the contribution of each light group and the
+    /// background accumulated into separate linear layers.
+    pub fn render_light_groups(&self) -> LightGroupLayers {
+        let pixel_count = self.image_width * self.image_height;
+        let scale = self.camera.exposure() / self.samples_per_pixel as f64;
+        let rows: Vec<BTreeMap<LightSource, Vec<Colour>>> = (0..self.image_height)
+            .into_par_iter()
+            .map(|row| {
+                let mut rng = rngs::SmallRng::from_rng(rand::thread_rng()).unwrap();
+                let mut row_layers: BTreeMap<LightSource, Vec<Colour>> = BTreeMap::new();
+
+                for col in 0..self.image_width {
+                    self.for_each_sample(row, col, &mut rng, |r, weight, rng| {
+                        let throughput = Colour::new(weight, weight, weight);
+                        let media = MediumStack::new();
+                        self.trace(
+                            r,
+                            &media,
+                            self.max_depth,
+                            throughput,
+                            rng,
+                            &mut |source, c| {
+                                row_layers
+                                    .entry(source)
+                                    .or_insert_with(|| vec![Colour::zeros(); self.image_width])
+                                    [col] += c * scale;
+                            },
+                        );
+                    });
+                }
+
+                row_layers
+            })
+            .collect();
+
+        let mut layers = LightGroupLayers::new(self.image_width, self.image_height);
+        for (row, row_layers) in rows.into_iter().enumerate() {
+            for (source, colours) in row_layers {
+                let layer = match source {
+                    LightSource::Background => &mut layers.background,
+                    LightSource::Light(group) => layers
+                        .groups
+                        .entry(group.to_string())
+                        .or_insert_with(|| vec![Colour::zeros(); pixel_count]),
+                };
+                let offset = row * self.image_width;
+                layer[offset..offset + self.image_width].copy_from_slice(&colours);
             }
         }
-        pixel_colour /= self.samples_per_pixel as f64;
+        layers
+    }
+
+    /// Projects a point in the scene into the image, checking whether it is
+    /// hidden behind other objects. Returns `None` if the camera doesn't
+    /// support projecting points.
+    pub fn project(&self, point: &Point3) -> Option<ScreenPoint> {
+        let film = self.camera.project(point)?;
+        let x = film.s * (self.image_width - 1) as f64;
+        let y = film.t * (self.image_height - 1) as f64;
+
+        let visibility = if film.is_behind_camera() {
+            Visibility::BehindCamera
+        } else if !(0. ..self.image_width as f64).contains(&x)
+            || !(0. ..self.image_height as f64).contains(&y)
+        {
+            Visibility::OffScreen
+        } else {
+            let r = self.camera.get_ray(film.s, film.t, (0.5, 0.5), 0.);
+            let distance = (*point - r.origin).length();
+            let mut rng = rngs::SmallRng::seed_from_u64(0);
+            let ray_t = interval::Interval::new(0.001, distance - 0.001);
+            match self.world.hit(&r, &ray_t, &mut rng) {
+                Some(_) => Visibility::Occluded,
+                None => Visibility::Visible,
+            }
+        };
+
+        Some(ScreenPoint {
+            x,
+            y,
+            depth: film.depth,
+            visibility,
+        })
+    }
 
-        let r = (pixel_colour.x.sqrt() * 255.999) as u8;
-        let g = (pixel_colour.y.sqrt() * 255.999) as u8;
-        let b = (pixel_colour.z.sqrt() * 255.999) as u8;
+    /// Finds the closest object seen through the centre of a pixel. Lists
+    /// and BVHs are searched for the object inside them that was hit, while
+    /// other objects such as transforms and CSG are picked as a whole.
+    pub fn pick(&self, x: usize, y: usize) -> Option<Pick<'_>> {
+        let s = (x as f64 + 0.5) / (self.image_width - 1) as f64;
+        let t = (y as f64 + 0.5) / (self.image_height - 1) as f64;
+        let r = self.camera.get_ray(s, t, (0.5, 0.5), 0.);
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
 
-        (r, g, b)
+        let mut objects = self.world.children();
+        let mut pick = None;
+        loop {
+            let mut closest_so_far = f64::INFINITY;
+            let mut closest = None;
+            for object in objects {
+                let ray_t = interval::Interval::new(0.001, closest_so_far);
+                if let Some(hit) = object.hit(&r, &ray_t, &mut rng) {
+                    closest_so_far = hit.t;
+                    closest = Some(Pick { object, hit });
+                }
+            }
+
+            // Keep going into groups until reaching the object itself
+            let Some(closest) = closest else {
+                return pick;
+            };
+            objects = closest.object.children();
+            pick = Some(closest);
+            if objects.is_empty() {
+                return pick;
+            }
+        }
     }
 
-    fn ray_colour(&self, r: &Ray, depth: usize, rng: &mut rngs::SmallRng) -> Colour {
+    pub(crate) fn ray_colour(&self, r: &Ray, depth: usize, rng: &mut rngs::SmallRng) -> Colour {
+        let mut colour = Colour::zeros();
+        let media = MediumStack::new();
+        self.trace(
+            r,
+            &media,
+            depth,
+            Colour::new(1., 1., 1.),
+            rng,
+            &mut |_, c| colour += c,
+        );
+        colour
+    }
+
+    /// Follows a path through the scene, passing the light that reaches the
+    /// camera from each source along the path to `contribute`. The throughput
+    /// is the attenuation accumulated along the path so far, and `media` are
+    /// the media that the ray is travelling through.
+    fn trace<'a>(
+        &'a self,
+        r: &Ray,
+        media: &MediumStack<'a>,
+        depth: usize,
+        throughput: Colour,
+        rng: &mut rngs::SmallRng,
+        contribute: &mut impl FnMut(LightSource<'a>, Colour),
+    ) {
+        let bounces = self.max_depth - depth;
         if depth == 0 {
-            return Colour::new(0., 0., 0.);
+            stats::record_path(bounces, PathEnd::DepthLimit);
+            return;
         }
-        match self
+        stats::record_ray(bounces);
+        let hit = self
             .world
-            .hit(r, &interval::Interval::new(0.001, f64::INFINITY), rng)
-        {
+            .hit(r, &interval::Interval::new(0.001, f64::INFINITY), rng);
+
+        // Inside a scattering medium, the ray may collide with the volume before
+        // it reaches the next surface. Tentative collisions are sampled using
+        // the highest extinction of any channel, and each is either real or a
+        // null collision that the ray continues straight through.
+        let mut throughput = throughput;
+        if let Some(scattering) = media.current().and_then(|medium| medium.scattering) {
+            let surface = hit.as_ref().map_or(f64::INFINITY, |hitrec| hitrec.t);
+            let mut distance = 0.;
+            loop {
+                distance += material::free_flight_distance(scattering.majorant(), rng);
+                if distance >= surface {
+                    break;
+                }
+                let (real, weight) = scattering.sample_collision(throughput, rng);
+                throughput *= weight;
+                if !real {
+                    continue;
+                }
+
+                let collision = HitRecord::new(
+                    r.at(distance),
+                    Vec3::new(1., 0., 0.),
+                    distance,
+                    0.,
+                    0.,
+                    &scattering.phase_function,
+                );
+                let scatter_result = scattering.phase_function.scatter(r, &collision, rng);
+                return match scatter_result.behaviour {
+                    Behaviour::Scatter => self.trace(
+                        &scatter_result.scattered,
+                        media,
+                        depth - 1,
+                        throughput * scatter_result.attenuation,
+                        rng,
+                        contribute,
+                    ),
+                    Behaviour::Absorb => {
+                        stats::record_path(bounces + 1, PathEnd::Absorbed);
+                    }
+                };
+            }
+        }
+
+        match hit {
             Some(hitrec) => {
                 // Ray intersects object
+                let medium = hitrec
+                    .mat
+                    .medium()
+                    .map(|medium| (MediumStack::medium_id(hitrec.mat), medium));
+
+                if let Some((id, medium)) = medium {
+                    if !media.is_true_intersection(id, &medium) {
+                        // The surface is inside a higher priority medium, so the
+                        // ray continues straight through it
+                        let continued = Ray::new(hitrec.point, r.direction, r.time);
+                        let media = media.crossed(id, medium, hitrec.front_face);
+                        return self.trace(
+                            &continued,
+                            &media,
+                            depth - 1,
+                            throughput,
+                            rng,
+                            contribute,
+                        );
+                    }
+                }
+
                 let emitted = hitrec.mat.emitted(hitrec.u, hitrec.v, &hitrec.point);
-                let scatter_result = hitrec.mat.scatter(r, &hitrec, rng);
+                if emitted != Colour::zeros() {
+                    contribute(
+                        LightSource::Light(hitrec.mat.light_group()),
+                        throughput * emitted,
+                    );
+                }
+                let scatter_result = hitrec.mat.scatter_in_media(r, &hitrec, media, rng);
+
+                // Track which media the scattered ray is inside, which changes if
+                // it passed through the surface
+                let media = match medium {
+                    Some((id, medium))
+                        if scatter_result.scattered.direction.dot(hitrec.normal) < 0. =>
+                    {
+                        media.crossed(id, medium, hitrec.front_face)
+                    }
+                    _ => *media,
+                };
 
                 match scatter_result.behaviour {
-                    Behaviour::Scatter => {
-                        emitted
-                            + scatter_result.attenuation
-                                * self.ray_colour(&scatter_result.scattered, depth - 1, rng)
+                    Behaviour::Scatter => self.trace(
+                        &scatter_result.scattered,
+                        &media,
+                        depth - 1,
+                        throughput * scatter_result.attenuation,
+                        rng,
+                        contribute,
+                    ),
+                    Behaviour::Absorb => {
+                        stats::record_path(bounces + 1, PathEnd::Absorbed);
                     }
-                    Behaviour::Absorb => emitted,
                 }
             }
             None => {
                 // Ray doesn't intersect any objects
-                self.background
+                stats::record_path(bounces + 1, PathEnd::Escaped);
+                contribute(LightSource::Background, throughput * self.background);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs, SeedableRng};
+
+    use crate::{
+        bvh::BVHNode, camera::CameraBuilder, material, object, ray::Ray, vec3::Vec3, Colour, Point3,
+    };
+
+    use super::{Scene, Visibility};
+
+    fn two_spheres() -> Scene {
+        scene_of(two_sphere_list())
+    }
+
+    fn two_sphere_list() -> object::HittableList {
+        let mut world = object::HittableList::new();
+        let grey = Arc::new(material::Lambertian::from_colour(Colour::new(
+            0.5, 0.5, 0.5,
+        )));
+        world.add(Arc::new(object::Sphere::new(
+            Point3::new(0., 0., 0.),
+            1.,
+            grey.clone(),
+        )));
+        world.add(Arc::new(object::Sphere::new(
+            Point3::new(0., 0., -5.),
+            1.,
+            grey,
+        )));
+        world
+    }
+
+    fn scene_of(world: object::HittableList) -> Scene {
+        let camera = CameraBuilder::new()
+            .origin(Point3::new(0., 0., 5.))
+            .look_at(Point3::new(0., 0., 0.))
+            .aspect_ratio(1.)
+            .build();
+        Scene::new(world, camera, 5, 1, 11, 11, Colour::zeros())
+    }
+
+    #[test]
+    fn water_inside_glass() {
+        // A slab of glass from z = 0 to -2 overlapping a slab of water from
+        // z = -1 to -3. The glass has the higher priority, so the water surface
+        // inside it is ignored and the glass refracts straight into the water.
+        let mut world = object::HittableList::new();
+        let glass = Arc::new(material::Dielectric::new(1.5).with_priority(2));
+        let water = Arc::new(material::Dielectric::new(1.33).with_priority(1));
+        world.add(Arc::new(object::quad::new_box(
+            &Point3::new(-10., -10., -2.),
+            &Point3::new(10., 10., 0.),
+            glass,
+        )));
+        world.add(Arc::new(object::quad::new_box(
+            &Point3::new(-10., -10., -3.),
+            &Point3::new(10., 10., -1.),
+            water,
+        )));
+
+        // A thin strip of light in the water, where a ray entering the glass
+        // at 30 degrees should land after refracting into the water
+        let sin_air = 0.5_f64;
+        let tan = |sin: f64| sin / (1. - sin * sin).sqrt();
+        let x = tan(sin_air) + 2. * tan(sin_air / 1.5) + 0.5 * tan(sin_air / 1.33);
+        let light = Arc::new(material::DiffuseLight::from_colour(Colour::new(1., 1., 1.)));
+        world.add(Arc::new(object::Quad::new(
+            Point3::new(x - 0.02, -1., -2.5),
+            Vec3::new(0.04, 0., 0.),
+            Vec3::new(0., 2., 0.),
+            light,
+        )));
+        let scene = scene_of(world);
+
+        // Only rays reflected at one of the surfaces miss the light
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let r = Ray::new(
+            Point3::new(0., 0., 1.),
+            Vec3::new(sin_air, 0., -(1. - sin_air * sin_air).sqrt()),
+            0.,
+        );
+        let samples = 100;
+        let colour = (0..samples).fold(Colour::zeros(), |total, _| {
+            total + scene.ray_colour(&r, 5, &mut rng)
+        }) / samples as f64;
+        assert!(colour.x > 0.8, "{:?}", colour);
+    }
+
+    #[test]
+    fn reject_wrong_buffer_size() {
+        let scene = two_spheres();
+        let mut pixels = vec![0; 11 * 11 * 3 - 1];
+        let err = scene.render(&mut pixels).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn project_points() {
+        let scene = two_spheres();
+
+        let front = scene.project(&Point3::new(0., 0., 1.)).unwrap();
+        assert!((front.x - 5.).abs() < 1e-9);
+        assert!((front.y - 5.).abs() < 1e-9);
+        assert!((front.depth - 4.).abs() < 1e-9);
+        assert_eq!(front.visibility, Visibility::Visible);
+
+        let hidden = scene.project(&Point3::new(0., 0., -4.)).unwrap();
+        assert_eq!(hidden.visibility, Visibility::Occluded);
+
+        let aside = scene.project(&Point3::new(100., 0., 0.)).unwrap();
+        assert_eq!(aside.visibility, Visibility::OffScreen);
+
+        let behind = scene.project(&Point3::new(0., 0., 10.)).unwrap();
+        assert_eq!(behind.visibility, Visibility::BehindCamera);
+    }
+
+    #[test]
+    fn pick_closest_object() {
+        let scene = two_spheres();
+
+        let pick = scene.pick(4, 4).unwrap();
+        assert!(Arc::ptr_eq(pick.object, &scene.world.objects[0]));
+        assert!((pick.hit.point.z - 1.).abs() < 0.1);
+
+        assert!(scene.pick(0, 0).is_none());
+
+        // Objects are found inside a BVH
+        let mut rng = rngs::SmallRng::seed_from_u64(0);
+        let spheres = two_sphere_list();
+        let front = spheres.objects[0].clone();
+        let mut world = object::HittableList::new();
+        world.add(Arc::new(BVHNode::new(spheres, &mut rng)));
+        let scene = scene_of(world);
+        let pick = scene.pick(4, 4).unwrap();
+        assert!(Arc::ptr_eq(pick.object, &front));
+    }
+}
@@ -0,0 +1,263 @@
+//! Optional performance counters collected while rendering.
+//!
+//! Counters are recorded into a thread local [`RenderStats`] so that the hot
+//! path never contends on a lock, and are only recorded while at least one
+//! render has statistics enabled. The scene merges each thread's counters into
+//! a single summary once a row has finished rendering.
+
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+/// Number of renders currently collecting statistics.
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static LOCAL: RefCell<RenderStats> = RefCell::new(RenderStats::default());
+}
+
+/// How a light path through the scene ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathEnd {
+    /// The path was absorbed by a material.
+    Absorbed,
+    /// The path left the scene without hitting anything.
+    Escaped,
+    /// The path reached the maximum ray depth.
+    DepthLimit,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RenderStats {
+    /// Number of rays traced, indexed by the number of bounces before the ray.
+    pub rays_by_depth: Vec<u64>,
+    /// Number of calls to `Hittable::hit`, keyed by the type of the hittable.
+    pub hit_calls: BTreeMap<&'static str, u64>,
+    pub bvh_nodes_visited: u64,
+    pub aabb_tests: u64,
+    pub absorbed_paths: u64,
+    pub escaped_paths: u64,
+    pub depth_limited_paths: u64,
+    /// Total number of rays across all paths, used for the average path length.
+    pub path_rays: u64,
+    /// Wall clock time spent rendering.
+    pub elapsed: Duration,
+}
+
+impl RenderStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the counters from another set of statistics to this one. The
+    /// elapsed time is not merged, since threads render concurrently.
+    pub fn merge(&mut self, other: &Self) {
+        if self.rays_by_depth.len() < other.rays_by_depth.len() {
+            self.rays_by_depth.resize(other.rays_by_depth.len(), 0);
+        }
+        for (total, count) in self.rays_by_depth.iter_mut().zip(&other.rays_by_depth) {
+            *total += count;
+        }
+        for (name, count) in &other.hit_calls {
+            *self.hit_calls.entry(name).or_insert(0) += count;
+        }
+        self.bvh_nodes_visited += other.bvh_nodes_visited;
+        self.aabb_tests += other.aabb_tests;
+        self.absorbed_paths += other.absorbed_paths;
+        self.escaped_paths += other.escaped_paths;
+        self.depth_limited_paths += other.depth_limited_paths;
+        self.path_rays += other.path_rays;
+    }
+
+    pub fn total_rays(&self) -> u64 {
+        self.rays_by_depth.iter().sum()
+    }
+
+    pub fn total_paths(&self) -> u64 {
+        self.absorbed_paths + self.escaped_paths + self.depth_limited_paths
+    }
+
+    /// Average number of rays traced per path.
+    pub fn average_path_length(&self) -> f64 {
+        match self.total_paths() {
+            0 => 0.,
+            paths => self.path_rays as f64 / paths as f64,
+        }
+    }
+
+    pub fn rays_per_second(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            secs if secs > 0. => self.total_rays() as f64 / secs,
+            _ => 0.,
+        }
+    }
+
+    fn record_ray(&mut self, depth: usize) {
+        if self.rays_by_depth.len() <= depth {
+            self.rays_by_depth.resize(depth + 1, 0);
+        }
+        self.rays_by_depth[depth] += 1;
+    }
+
+    fn record_path(&mut self, length: usize, end: PathEnd) {
+        self.path_rays += length as u64;
+        match end {
+            PathEnd::Absorbed => self.absorbed_paths += 1,
+            PathEnd::Escaped => self.escaped_paths += 1,
+            PathEnd::DepthLimit => self.depth_limited_paths += 1,
+        }
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Render statistics")?;
+        writeln!(f, "  Elapsed:             {:.2?}", self.elapsed)?;
+        writeln!(
+            f,
+            "  Rays traced:         {} ({:.0} rays/s)",
+            self.total_rays(),
+            self.rays_per_second()
+        )?;
+        writeln!(f, "  Rays by depth:")?;
+        for (depth, count) in self.rays_by_depth.iter().enumerate() {
+            writeln!(f, "    {:>3}: {}", depth, count)?;
+        }
+        writeln!(
+            f,
+            "  Paths:               {} (absorbed {}, escaped {}, depth limit {})",
+            self.total_paths(),
+            self.absorbed_paths,
+            self.escaped_paths,
+            self.depth_limited_paths
+        )?;
+        writeln!(
+            f,
+            "  Average path length: {:.3}",
+            self.average_path_length()
+        )?;
+        writeln!(f, "  BVH nodes visited:   {}", self.bvh_nodes_visited)?;
+        writeln!(f, "  AABB tests:          {}", self.aabb_tests)?;
+        writeln!(f, "  Hit calls:")?;
+        for (name, count) in &self.hit_calls {
+            writeln!(f, "    {}: {}", name, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Starts recording counters on all threads until the returned guard is
+/// dropped.
+pub(crate) fn enable() -> ActiveGuard {
+    ACTIVE.fetch_add(1, Ordering::AcqRel);
+    ActiveGuard
+}
+
+pub(crate) struct ActiveGuard;
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        ACTIVE.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn record(f: impl FnOnce(&mut RenderStats)) {
+    if ACTIVE.load(Ordering::Relaxed) != 0 {
+        LOCAL.with(|stats| f(&mut stats.borrow_mut()));
+    }
+}
+
+/// Takes the counters recorded on the current thread, resetting them.
+pub(crate) fn take_local() -> RenderStats {
+    LOCAL.with(|stats| stats.take())
+}
+
+pub(crate) fn record_hit(name: &'static str) {
+    record(|s| *s.hit_calls.entry(name).or_insert(0) += 1);
+}
+
+pub(crate) fn record_bvh_node() {
+    record(|s| s.bvh_nodes_visited += 1);
+}
+
+pub(crate) fn record_aabb_test() {
+    record(|s| s.aabb_tests += 1);
+}
+
+pub(crate) fn record_ray(depth: usize) {
+    record(|s| s.record_ray(depth));
+}
+
+pub(crate) fn record_path(length: usize, end: PathEnd) {
+    record(|s| s.record_path(length, end));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{camera::CameraBuilder, material, object, scene::Scene, Colour, Point3};
+
+    use super::{PathEnd, RenderStats};
+
+    #[test]
+    fn merge_counters() {
+        let mut a = RenderStats::new();
+        a.record_ray(0);
+        a.record_ray(0);
+        a.record_ray(1);
+        a.record_path(2, PathEnd::Escaped);
+        a.hit_calls.insert("Sphere", 3);
+
+        let mut b = RenderStats::new();
+        b.record_ray(0);
+        b.record_ray(1);
+        b.record_ray(2);
+        b.record_path(3, PathEnd::Absorbed);
+        b.record_path(1, PathEnd::DepthLimit);
+        b.hit_calls.insert("Sphere", 1);
+        b.hit_calls.insert("Quad", 2);
+
+        a.merge(&b);
+
+        assert_eq!(a.rays_by_depth, vec![3, 2, 1]);
+        assert_eq!(a.total_rays(), 6);
+        assert_eq!(a.total_paths(), 3);
+        assert_eq!(a.average_path_length(), 2.);
+        assert_eq!(a.hit_calls["Sphere"], 4);
+        assert_eq!(a.hit_calls["Quad"], 2);
+    }
+
+    #[test]
+    fn collect_during_render() {
+        let mut world = object::HittableList::new();
+        world.add(Arc::new(object::Sphere::new(
+            Point3::new(0., 0., 0.),
+            0.5,
+            Arc::new(material::DiffuseLight::from_colour(Colour::new(1., 1., 1.))),
+        )));
+        let camera = CameraBuilder::new()
+            .origin(Point3::new(0., 0., -3.))
+            .look_at(Point3::new(0., 0., 0.))
+            .build();
+        let mut scene = Scene::new(world, camera, 5, 1, 8, 6, Colour::zeros());
+        scene.collect_stats(true);
+
+        let mut pixels = vec![0; 8 * 6 * 3];
+        scene.render(&mut pixels).unwrap();
+        let stats = scene.stats().unwrap();
+
+        // Every path is a single ray that either hits the light or escapes
+        assert_eq!(stats.total_rays(), 48);
+        assert_eq!(stats.total_paths(), 48);
+        assert_eq!(stats.average_path_length(), 1.);
+        assert!(stats.absorbed_paths > 0);
+        assert!(stats.escaped_paths > 0);
+        assert_eq!(stats.hit_calls["HittableList"], 48);
+        assert_eq!(stats.hit_calls["Sphere"], 48);
+    }
+}
@@ -0,0 +1,151 @@
+use std::{fmt, sync::Arc};
+
+use crate::{aabb::AABB, Point3};
+
+use super::Texture;
+
+/// A scalar field giving the density of a participating medium at each point
+/// in space.
+pub trait DensityField: fmt::Debug + Send + Sync {
+    fn density(&self, p: &Point3) -> f64;
+
+    /// An upper bound on the density anywhere inside the field, used as the
+    /// majorant when sampling the medium.
+    fn max_density(&self) -> f64;
+}
+
+/// A density field driven by a texture, such as `NoiseTexture`. The density
+/// at a point is the average of the texture's colour channels, clamped to the
+/// range [0, 1], multiplied by `scale`.
+#[derive(Debug)]
+pub struct TextureDensity {
+    texture: Arc<dyn Texture>,
+    scale: f64,
+}
+
+impl TextureDensity {
+    pub fn new(texture: Arc<dyn Texture>, scale: f64) -> Self {
+        Self { texture, scale }
+    }
+}
+
+impl DensityField for TextureDensity {
+    fn density(&self, p: &Point3) -> f64 {
+        let value = self.texture.get_value(0., 0., p);
+        ((value.x + value.y + value.z) / 3.).clamp(0., 1.) * self.scale
+    }
+
+    fn max_density(&self) -> f64 {
+        self.scale
+    }
+}
+
+/// A density field sampled from a regular grid of voxels spanning a bounding
+/// box, with trilinear interpolation between voxel centres. Outside of the
+/// box the density is zero.
+#[derive(Debug)]
+pub struct VoxelGrid {
+    bounds: AABB,
+    resolution: [usize; 3],
+    densities: Vec<f64>,
+    max_density: f64,
+}
+
+impl VoxelGrid {
+    /// Creates a new grid from densities stored with x varying fastest, then
+    /// y, then z.
+    pub fn new(bounds: AABB, resolution: [usize; 3], densities: Vec<f64>) -> Self {
+        assert_eq!(
+            densities.len(),
+            resolution[0] * resolution[1] * resolution[2],
+            "voxel grid has the wrong number of densities for its resolution"
+        );
+        let max_density = densities.iter().cloned().fold(0., f64::max);
+        Self {
+            bounds,
+            resolution,
+            densities,
+            max_density,
+        }
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.densities[(z * self.resolution[1] + y) * self.resolution[0] + x]
+    }
+}
+
+impl DensityField for VoxelGrid {
+    fn density(&self, p: &Point3) -> f64 {
+        let mut index = [0; 3];
+        let mut frac = [0.; 3];
+        for axis in 0..3 {
+            let interval = self.bounds.axis(axis);
+            if !interval.contains(p[axis]) {
+                return 0.;
+            }
+
+            // Position relative to the voxel centres
+            let n = self.resolution[axis];
+            let g = ((p[axis] - interval.min) / interval.size() * n as f64 - 0.5)
+                .clamp(0., (n - 1) as f64);
+            index[axis] = (g.floor() as usize).min(n.saturating_sub(2));
+            frac[axis] = if n > 1 { g - index[axis] as f64 } else { 0. };
+        }
+
+        let mut density = 0.;
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let mut weight = 1.;
+            let mut voxel = [0; 3];
+            for axis in 0..3 {
+                weight *= if offset[axis] == 1 {
+                    frac[axis]
+                } else {
+                    1. - frac[axis]
+                };
+                voxel[axis] = (index[axis] + offset[axis]).min(self.resolution[axis] - 1);
+            }
+            if weight > 0. {
+                density += weight * self.voxel(voxel[0], voxel[1], voxel[2]);
+            }
+        }
+        density
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_density
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{aabb::AABB, texture::SolidColour, Colour, Point3};
+
+    use super::{DensityField, TextureDensity, VoxelGrid};
+
+    #[test]
+    fn texture_density_stays_under_majorant() {
+        let p = Point3::zeros();
+        let bright = TextureDensity::new(Arc::new(SolidColour::new(Colour::new(2., 2., 2.))), 3.);
+        assert_eq!(bright.density(&p), bright.max_density());
+
+        let negative =
+            TextureDensity::new(Arc::new(SolidColour::new(Colour::new(-1., 0., 0.))), 3.);
+        assert_eq!(negative.density(&p), 0.);
+    }
+
+    #[test]
+    fn voxel_grid_interpolates() {
+        let bounds = AABB::from_points(Point3::new(0., 0., 0.), Point3::new(2., 1., 1.));
+        let grid = VoxelGrid::new(bounds, [2, 1, 1], vec![0., 1.]);
+
+        assert_eq!(grid.max_density(), 1.);
+        assert_eq!(grid.density(&Point3::new(0.5, 0.5, 0.5)), 0.);
+        assert_eq!(grid.density(&Point3::new(1., 0.5, 0.5)), 0.5);
+        assert_eq!(grid.density(&Point3::new(1.5, 0.5, 0.5)), 1.);
+        assert_eq!(grid.density(&Point3::new(1.9, 0.2, 0.7)), 1.);
+        assert_eq!(grid.density(&Point3::new(3., 0.5, 0.5)), 0.);
+    }
+}
@@ -7,7 +7,9 @@ use super::Texture;
 #[derive(Debug)]
 pub struct ImageTexture {
     buf: Vec<u8>,
-    info: png::OutputInfo,
+    width: usize,
+    height: usize,
+    channels: usize,
 }
 
 impl ImageTexture {
@@ -15,12 +17,53 @@ impl ImageTexture {
     where
         P: AsRef<Path>,
     {
-        let decoder = png::Decoder::new(File::open(path).unwrap());
-        let mut reader = decoder.read_info().unwrap();
+        Self::open(path).unwrap()
+    }
+
+    /// Loads a PNG image, returning an error if it can't be read. Images of
+    /// any bit depth are converted to 8 bits, and palettes are expanded.
+    pub fn open<P>(path: P) -> Result<Self, png::DecodingError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut decoder = png::Decoder::new(File::open(path)?);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info()?;
         let mut buf = vec![0; reader.output_buffer_size()];
-        let info = reader.next_frame(&mut buf).unwrap();
+        let info = reader.next_frame(&mut buf)?;
         buf.truncate(info.buffer_size());
-        Self { buf, info }
+        Ok(Self::from_pixels(
+            info.width as usize,
+            info.height as usize,
+            info.color_type.samples(),
+            buf,
+        ))
+    }
+
+    /// Creates a texture from 8 bit pixels in row-major order, starting at
+    /// the top left. Images with fewer than three channels are treated as
+    /// greyscale.
+    pub fn from_pixels(width: usize, height: usize, channels: usize, buf: Vec<u8>) -> Self {
+        assert!(channels > 0, "expected at least one channel");
+        assert_eq!(
+            buf.len(),
+            width * height * channels,
+            "pixel buffer doesn't match the image size"
+        );
+        Self {
+            buf,
+            width,
+            height,
+            channels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
     }
 }
 
@@ -29,14 +72,16 @@ impl Texture for ImageTexture {
         let u = u.clamp(0., 1.);
         let v = 1. - v.clamp(0., 1.);
 
-        let i = (u * self.info.width as f64) as usize;
-        let j = (v * self.info.height as f64) as usize;
-
-        let bytes_per_pixel = self.info.color_type.samples();
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
 
-        let pixel_offset = j * self.info.width as usize + i;
-        let pixel = &self.buf[bytes_per_pixel * pixel_offset..bytes_per_pixel * (pixel_offset + 1)];
+        let pixel_offset = j * self.width + i;
+        let pixel = &self.buf[self.channels * pixel_offset..self.channels * (pixel_offset + 1)];
         let colour_scale = 1.0 / 255.0;
+        if self.channels < 3 {
+            let grey = pixel[0] as f64 * colour_scale;
+            return Colour::new(grey, grey, grey);
+        }
         Colour::new(
             pixel[0] as f64 * colour_scale,
             pixel[1] as f64 * colour_scale,
@@ -44,3 +89,37 @@ impl Texture for ImageTexture {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+
+    use crate::{texture::Texture, Colour, Point3};
+
+    use super::ImageTexture;
+
+    #[test]
+    fn open_16_bit_image() {
+        // A 2x1 greyscale image with a white and a mid grey pixel
+        let path = std::env::temp_dir().join(format!("lumiere-16-bit-{}.png", std::process::id()));
+        let mut encoder = png::Encoder::new(File::create(&path).unwrap(), 2, 1);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Sixteen);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0xff, 0xff, 0x80, 0x00]).unwrap();
+        drop(writer);
+
+        let image = ImageTexture::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((image.width(), image.height()), (2, 1));
+        let p = Point3::zeros();
+        assert!(image
+            .get_value(0.25, 0.5, &p)
+            .is_close(&Colour::new(1., 1., 1.)));
+        let grey = 128. / 255.;
+        assert!(image
+            .get_value(0.75, 0.5, &p)
+            .is_close(&Colour::new(grey, grey, grey)));
+    }
+}
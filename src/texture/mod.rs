@@ -1,17 +1,27 @@
 use std::fmt;
 
 pub mod checker;
+pub mod density;
 pub mod image;
 pub mod noise;
 pub mod solid;
+pub mod vertex_colour;
 
 pub use self::noise::NoiseTexture;
 pub use checker::CheckerTexture;
+pub use density::{DensityField, TextureDensity, VoxelGrid};
 pub use image::ImageTexture;
 pub use solid::SolidColour;
+pub use vertex_colour::VertexColourTexture;
 
-use crate::{Colour, Point3};
+use crate::{object::HitRecord, Colour, Point3};
 
 pub trait Texture: fmt::Debug + Send + Sync {
     fn get_value(&self, u: f64, v: f64, p: &Point3) -> Colour;
+
+    /// The texture's value at a hit. By default this looks up the hit's
+    /// texture coordinates and point.
+    fn value(&self, hitrec: &HitRecord) -> Colour {
+        self.get_value(hitrec.u, hitrec.v, &hitrec.point)
+    }
 }
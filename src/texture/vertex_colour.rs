@@ -0,0 +1,28 @@
+use crate::{object::HitRecord, Colour, Point3};
+
+use super::Texture;
+
+/// The per-vertex colours of a triangle mesh, interpolated across its
+/// triangles. Set the colours with [`TriangleMesh::with_colours`].
+///
+/// Hits on objects without vertex colours are black.
+///
+/// [`TriangleMesh::with_colours`]: crate::object::TriangleMesh::with_colours
+#[derive(Debug, Default)]
+pub struct VertexColourTexture;
+
+impl VertexColourTexture {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Texture for VertexColourTexture {
+    fn get_value(&self, _u: f64, _v: f64, _p: &Point3) -> Colour {
+        Colour::zeros()
+    }
+
+    fn value(&self, hitrec: &HitRecord) -> Colour {
+        hitrec.colour.unwrap_or(Colour::zeros())
+    }
+}